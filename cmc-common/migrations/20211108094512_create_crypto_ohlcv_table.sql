@@ -0,0 +1,27 @@
+-- Create the tables used by the historical backfill
+
+CREATE TABLE crypto_ohlcv (
+    id INTEGER REFERENCES crypto_map,
+    -- Either `hourly` or `daily`.
+    time_period TEXT NOT NULL,
+    quote TEXT NOT NULL,
+    time_open timestamptz NOT NULL,
+    time_close timestamptz NOT NULL,
+    open NUMERIC,
+    high NUMERIC,
+    low NUMERIC,
+    close NUMERIC,
+    volume NUMERIC,
+    market_cap NUMERIC,
+
+    PRIMARY KEY (id, time_period, quote, time_open)
+);
+
+-- Last timestamp stored by the backfill of each endpoint, used to resume it.
+CREATE TABLE backfill_progress (
+    id INTEGER REFERENCES crypto_map,
+    endpoint TEXT NOT NULL,
+    last_timestamp timestamptz NOT NULL,
+
+    PRIMARY KEY (id, endpoint)
+);
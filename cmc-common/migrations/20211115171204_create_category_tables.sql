@@ -0,0 +1,36 @@
+-- Create the tables of coin categories and normalize the tags of each cryptocurrency
+
+CREATE TABLE category (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    title TEXT,
+    description TEXT,
+    num_tokens INTEGER,
+    avg_price_change NUMERIC,
+    market_cap NUMERIC,
+    market_cap_change NUMERIC,
+    volume NUMERIC,
+    volume_change NUMERIC,
+    last_updated timestamptz NOT NULL
+);
+
+CREATE TABLE crypto_category (
+    category_id TEXT REFERENCES category ON DELETE CASCADE,
+    crypto_id INTEGER REFERENCES crypto_map ON DELETE CASCADE,
+
+    PRIMARY KEY (category_id, crypto_id)
+);
+
+CREATE TABLE crypto_tag (
+    id INTEGER REFERENCES crypto_map ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+
+    PRIMARY KEY (id, tag)
+);
+
+-- Move the tags out of the listings.
+INSERT INTO crypto_tag
+SELECT DISTINCT id, unnest(tags) FROM crypto_listing
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE crypto_listing DROP COLUMN tags;
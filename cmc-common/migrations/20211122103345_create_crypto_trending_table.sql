@@ -0,0 +1,17 @@
+-- Create a table with the snapshots of the trending feeds
+
+CREATE TABLE crypto_trending (
+    -- One of `gainers`, `losers`, `latest` or `most_visited`.
+    feed TEXT NOT NULL,
+    time_period TEXT NOT NULL,
+    fetched_at timestamptz NOT NULL,
+    rank INTEGER NOT NULL,
+    id INTEGER REFERENCES crypto_map ON DELETE CASCADE,
+    quote TEXT,
+    price NUMERIC,
+    -- Percent change over `time_period`.
+    percent_change NUMERIC,
+    last_updated timestamptz NOT NULL,
+
+    PRIMARY KEY (feed, time_period, fetched_at, rank)
+);
@@ -0,0 +1,10 @@
+-- Keep inactive and untracked cryptocurrencies in the map
+
+-- Untracked cryptocurrencies have no historical data yet.
+ALTER TABLE crypto_map ALTER COLUMN first_historical_data DROP NOT NULL;
+ALTER TABLE crypto_map ALTER COLUMN last_historical_data DROP NOT NULL;
+
+-- One of `active`, `inactive` or `untracked`.
+ALTER TABLE crypto_map ADD COLUMN status TEXT;
+-- When the cryptocurrency was found to be no longer active.
+ALTER TABLE crypto_map ADD COLUMN delisted_at timestamptz;
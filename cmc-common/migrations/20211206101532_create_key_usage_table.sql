@@ -0,0 +1,17 @@
+-- Plan limits and usage of the API key, as reported by `/v1/key/info`
+
+CREATE TABLE key_usage(
+  fetched_at timestamptz NOT NULL PRIMARY KEY,
+  -- Plan limits (the daily limit is not enforced by every plan).
+  credit_limit_daily INTEGER,
+  credit_limit_monthly INTEGER NOT NULL,
+  credit_limit_monthly_reset_timestamp timestamptz NOT NULL,
+  rate_limit_minute INTEGER NOT NULL,
+  -- Current usage.
+  requests_made_minute INTEGER NOT NULL,
+  requests_left_minute INTEGER NOT NULL,
+  credits_used_day INTEGER NOT NULL,
+  credits_left_day INTEGER,
+  credits_used_month INTEGER NOT NULL,
+  credits_left_month INTEGER NOT NULL
+);
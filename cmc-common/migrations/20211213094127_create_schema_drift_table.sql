@@ -0,0 +1,14 @@
+-- Differences between the responses of the CoinMarketCap API and the model of the client
+
+CREATE TABLE schema_drift(
+  endpoint TEXT NOT NULL,
+  -- Either `unknown` (i.e. a new field) or `missing` (i.e. an expected field not returned).
+  kind TEXT NOT NULL,
+  -- Dot-separated path of the field, where `*` denotes any element of an array or map.
+  path TEXT NOT NULL,
+  first_seen timestamptz NOT NULL,
+  last_seen timestamptz NOT NULL,
+  -- Number of responses in which the drift was detected.
+  occurrences INTEGER NOT NULL,
+  PRIMARY KEY (endpoint, kind, path)
+);
@@ -0,0 +1,34 @@
+-- Split the platforms (i.e. base blockchains) from the token contracts deployed on them
+
+-- `crypto_platform` was keyed by the token's own id, so it can't describe tokens deployed on several
+-- chains. Its rows lack the names of the chains and are rebuilt by the next update of the map.
+ALTER TABLE crypto_map DROP CONSTRAINT crypto_map_platform_fkey;
+ALTER TABLE crypto_listing DROP CONSTRAINT crypto_listing_platform_fkey;
+UPDATE crypto_map SET platform = NULL;
+UPDATE crypto_listing SET platform = NULL;
+DROP TABLE crypto_platform;
+
+CREATE TABLE platform (
+    id SERIAL PRIMARY KEY,
+    -- Name of the chain, e.g. `Ethereum` or `Binance Smart Chain (BEP20)`.
+    name TEXT NOT NULL UNIQUE,
+    -- crypto_map's id of the native coin of the chain (not unique, e.g. BEP2 and BEP20 share BNB).
+    coin_id INTEGER,
+    symbol TEXT,
+    slug TEXT
+);
+
+CREATE TABLE token_contract (
+    id INTEGER NOT NULL REFERENCES crypto_map (id),
+    platform_id INTEGER NOT NULL REFERENCES platform (id),
+    contract_address TEXT NOT NULL,
+    PRIMARY KEY (platform_id, contract_address)
+);
+
+CREATE INDEX token_contract_id_idx ON token_contract (id);
+-- Addresses are looked up case-insensitively (e.g. checksummed and lowercase EVM addresses).
+CREATE INDEX token_contract_address_idx ON token_contract (lower(contract_address));
+
+-- The platform on which the token was originally issued, according to the map.
+ALTER TABLE crypto_map ADD FOREIGN KEY (platform) REFERENCES platform (id);
+ALTER TABLE crypto_listing ADD FOREIGN KEY (platform) REFERENCES platform (id);
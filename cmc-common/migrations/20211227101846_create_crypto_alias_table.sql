@@ -0,0 +1,21 @@
+-- Keep the history of the names, symbols, slugs and ranks of each cryptocurrency
+
+CREATE TABLE crypto_alias (
+    id INTEGER NOT NULL REFERENCES crypto_map (id),
+    name TEXT NOT NULL,
+    symbol TEXT,
+    slug TEXT,
+    rank INTEGER,
+    valid_from timestamptz NOT NULL,
+    -- `NULL` while the values are still current.
+    valid_to timestamptz,
+    PRIMARY KEY (id, valid_from)
+);
+
+CREATE UNIQUE INDEX crypto_alias_current_idx ON crypto_alias (id) WHERE valid_to IS NULL;
+CREATE INDEX crypto_alias_symbol_idx ON crypto_alias (symbol);
+
+-- The current values are assumed to hold since the first historical data.
+INSERT INTO crypto_alias
+SELECT id, name, symbol, slug, rank, COALESCE(first_historical_data, now()), NULL
+FROM crypto_map;
@@ -0,0 +1,19 @@
+-- Audit log of the ingestion runs, i.e. each fetch of the latest listings
+
+CREATE TABLE ingest_run(
+  id BIGSERIAL PRIMARY KEY,
+  -- Endpoint of the CoinMarketCap API and its query parameters (e.g. `start=1&limit=100`).
+  endpoint TEXT NOT NULL,
+  params TEXT NOT NULL,
+  started_at timestamptz NOT NULL,
+  -- Missing while the run is in progress (or if the process died during the run).
+  finished_at timestamptz,
+  -- Rows written across all the tables of the run (e.g. new cryptocurrencies in `crypto_map`).
+  rows_inserted INTEGER NOT NULL DEFAULT 0,
+  rows_updated INTEGER NOT NULL DEFAULT 0,
+  credits_used INTEGER NOT NULL DEFAULT 0,
+  -- Missing if the run succeeded.
+  error TEXT
+);
+
+CREATE INDEX ingest_run_started_at_idx ON ingest_run (started_at DESC);
@@ -0,0 +1,23 @@
+-- Snapshots of the global market metrics, as reported by `/v1/global-metrics/quotes/latest`
+
+CREATE TABLE global_metrics(
+  last_updated timestamptz NOT NULL PRIMARY KEY,
+  active_cryptocurrencies INTEGER NOT NULL,
+  total_cryptocurrencies INTEGER,
+  active_market_pairs INTEGER NOT NULL,
+  active_exchanges INTEGER NOT NULL,
+  total_exchanges INTEGER,
+  -- Percentages of the total market cap.
+  btc_dominance NUMERIC,
+  eth_dominance NUMERIC,
+  defi_volume_24h NUMERIC,
+  defi_market_cap NUMERIC,
+  stablecoin_volume_24h NUMERIC,
+  stablecoin_market_cap NUMERIC,
+  derivatives_volume_24h NUMERIC,
+  -- Quoted in USD.
+  total_market_cap NUMERIC,
+  total_volume_24h NUMERIC,
+  altcoin_market_cap NUMERIC,
+  altcoin_volume_24h NUMERIC
+);
@@ -0,0 +1,24 @@
+-- Listings that failed the validation (see `validation`), kept for review along with the reasons
+
+CREATE TABLE crypto_listing_quarantine(
+  id INTEGER NOT NULL,
+  symbol TEXT NOT NULL,
+  last_updated timestamptz NOT NULL,
+  price NUMERIC,
+  -- Price of the previous snapshot in `crypto_listing`, if any.
+  previous_price NUMERIC,
+  circulating_supply NUMERIC,
+  total_supply NUMERIC,
+  max_supply NUMERIC,
+  reasons TEXT[] NOT NULL,
+  quarantined_at timestamptz NOT NULL DEFAULT now(),
+  -- Missing if not quarantined by an ingestion run (e.g. during an import).
+  ingest_run BIGINT REFERENCES ingest_run ON DELETE SET NULL,
+
+  PRIMARY KEY (id, last_updated)
+);
+
+-- Listings left out of `crypto_listing` by the validation, either dropped or quarantined.
+ALTER TABLE ingest_run
+  ADD COLUMN rows_rejected INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN rows_quarantined INTEGER NOT NULL DEFAULT 0;
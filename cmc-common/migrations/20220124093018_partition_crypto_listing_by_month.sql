@@ -0,0 +1,88 @@
+-- Partition the history of the listings by month, so that old snapshots are dropped along with their
+-- partition once rolled up into hourly and daily aggregates (see `retention`)
+
+-- The primary key of the new table takes the name of the index.
+ALTER TABLE crypto_listing RENAME TO crypto_listing_unpartitioned;
+ALTER INDEX crypto_listing_pkey RENAME TO crypto_listing_unpartitioned_pkey;
+
+CREATE TABLE crypto_listing (
+    LIKE crypto_listing_unpartitioned INCLUDING DEFAULTS,
+    PRIMARY KEY (id, last_updated),
+    CONSTRAINT crypto_listing_id_fkey FOREIGN KEY (id) REFERENCES crypto_map (id),
+    CONSTRAINT crypto_listing_platform_fkey FOREIGN KEY (platform) REFERENCES platform (id)
+) PARTITION BY RANGE (last_updated);
+
+-- Holds the snapshots of the months without a partition yet (e.g. backfilled history), until the
+-- retention job moves them to their own partition.
+CREATE TABLE crypto_listing_default PARTITION OF crypto_listing DEFAULT;
+
+-- Creates the partition of `crypto_listing` holding the month (in UTC) of `month`, named after it
+-- (e.g. `crypto_listing_2022_01`), and moves there the snapshots of that month stored in the default
+-- partition. Returns whether the partition was missing.
+CREATE FUNCTION create_crypto_listing_partition(month timestamptz) RETURNS BOOLEAN AS $$
+DECLARE
+    -- Computed without time zone, since adding a month to a timestamptz depends on the session's.
+    first_day timestamp := date_trunc('month', month AT TIME ZONE 'UTC');
+    lower_bound timestamptz := first_day AT TIME ZONE 'UTC';
+    upper_bound timestamptz := (first_day + interval '1 month') AT TIME ZONE 'UTC';
+    partition_name TEXT := 'crypto_listing_' || to_char(first_day, 'YYYY_MM');
+BEGIN
+    IF to_regclass(partition_name) IS NOT NULL THEN
+        RETURN FALSE;
+    END IF;
+
+    -- Keeps new snapshots of that month out of the default partition until the partition is attached.
+    LOCK TABLE crypto_listing_default IN SHARE ROW EXCLUSIVE MODE;
+    EXECUTE format('CREATE TABLE %I (LIKE crypto_listing INCLUDING DEFAULTS)', partition_name);
+    EXECUTE format(
+        'WITH moved AS (
+             DELETE FROM crypto_listing_default
+             WHERE last_updated >= $1 AND last_updated < $2
+             RETURNING *
+         )
+         INSERT INTO %I SELECT * FROM moved',
+        partition_name
+    ) USING lower_bound, upper_bound;
+    EXECUTE format(
+        'ALTER TABLE crypto_listing ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)',
+        partition_name, lower_bound, upper_bound
+    );
+
+    RETURN TRUE;
+END;
+$$ LANGUAGE plpgsql;
+
+INSERT INTO crypto_listing SELECT * FROM crypto_listing_unpartitioned;
+SELECT create_crypto_listing_partition(month)
+FROM (
+    SELECT DISTINCT date_trunc('month', last_updated AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS month
+    FROM crypto_listing
+) AS months;
+-- The current month, so that the next snapshots don't land in the default partition.
+SELECT create_crypto_listing_partition(now());
+DROP TABLE crypto_listing_unpartitioned;
+
+-- Aggregates of the snapshots of each cryptocurrency per hour, kept after the raw snapshots are
+-- dropped.
+CREATE TABLE crypto_listing_hourly (
+    id INTEGER NOT NULL REFERENCES crypto_map (id),
+    -- Start of the hour (or day) in UTC.
+    bucket timestamptz NOT NULL,
+    -- Prices of the first and last snapshots, along with the highest and lowest ones.
+    open NUMERIC NOT NULL,
+    high NUMERIC NOT NULL,
+    low NUMERIC NOT NULL,
+    close NUMERIC NOT NULL,
+    -- Average of the 24h volumes of the snapshots.
+    volume_24h NUMERIC,
+    -- Market cap of the last snapshot that has one.
+    market_cap NUMERIC,
+    -- Number of snapshots aggregated.
+    snapshots INTEGER NOT NULL,
+
+    PRIMARY KEY (id, bucket)
+);
+
+-- Same aggregates per day.
+CREATE TABLE crypto_listing_daily (LIKE crypto_listing_hourly INCLUDING ALL);
+ALTER TABLE crypto_listing_daily ADD FOREIGN KEY (id) REFERENCES crypto_map (id);
@@ -0,0 +1,49 @@
+-- Latest snapshot of each cryptocurrency, so that the consumers of the latest listings (e.g.
+-- `crypto-forex`) don't scan the whole history of `crypto_listing`
+CREATE TABLE crypto_listing_latest (
+    LIKE crypto_listing INCLUDING DEFAULTS,
+    PRIMARY KEY (id),
+    FOREIGN KEY (id) REFERENCES crypto_map (id),
+    FOREIGN KEY (platform) REFERENCES platform (id)
+);
+
+CREATE INDEX crypto_listing_latest_cmc_rank_idx ON crypto_listing_latest (cmc_rank);
+
+-- Keeps `crypto_listing_latest` up to date with every snapshot inserted into `crypto_listing`,
+-- unless an older one (e.g. backfilled history).
+CREATE FUNCTION update_crypto_listing_latest() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO crypto_listing_latest VALUES (NEW.*)
+    ON CONFLICT (id) DO UPDATE
+    SET num_market_pairs = EXCLUDED.num_market_pairs,
+        max_supply = EXCLUDED.max_supply,
+        circulating_supply = EXCLUDED.circulating_supply,
+        total_supply = EXCLUDED.total_supply,
+        platform = EXCLUDED.platform,
+        cmc_rank = EXCLUDED.cmc_rank,
+        quote = EXCLUDED.quote,
+        price = EXCLUDED.price,
+        volume_24h = EXCLUDED.volume_24h,
+        volume_change_24h = EXCLUDED.volume_change_24h,
+        percent_change_1h = EXCLUDED.percent_change_1h,
+        percent_change_24h = EXCLUDED.percent_change_24h,
+        percent_change_7d = EXCLUDED.percent_change_7d,
+        percent_change_30d = EXCLUDED.percent_change_30d,
+        percent_change_60d = EXCLUDED.percent_change_60d,
+        percent_change_90d = EXCLUDED.percent_change_90d,
+        market_cap = EXCLUDED.market_cap,
+        market_cap_dominance = EXCLUDED.market_cap_dominance,
+        fully_diluted_market_cap = EXCLUDED.fully_diluted_market_cap,
+        last_updated = EXCLUDED.last_updated
+    WHERE crypto_listing_latest.last_updated < EXCLUDED.last_updated;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER crypto_listing_latest_update
+    AFTER INSERT ON crypto_listing
+    FOR EACH ROW EXECUTE FUNCTION update_crypto_listing_latest();
+
+INSERT INTO crypto_listing_latest
+SELECT DISTINCT ON (id) * FROM crypto_listing ORDER BY id, last_updated DESC;
@@ -0,0 +1,33 @@
+//! Settings of the database shared by the applications.
+
+use serde::Deserialize;
+use sqlx::postgres::PgConnectOptions;
+use sqlx::ConnectOptions;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DbConfig {
+    pub username: String,
+    pub password: String,
+    pub port: u16,
+    pub host: String,
+    pub database_name: String,
+    /// Whether to create the database (if missing) and run the embedded migrations at startup.
+    #[serde(default)]
+    pub migrate: bool,
+}
+
+impl DbConfig {
+    pub fn without_db(&self) -> PgConnectOptions {
+        PgConnectOptions::new()
+            .host(&self.host)
+            .username(&self.username)
+            .password(&self.password)
+            .port(self.port)
+    }
+
+    pub fn with_db(&self) -> PgConnectOptions {
+        let mut options = self.without_db().database(&self.database_name);
+        options.log_statements(log::LevelFilter::Trace);
+        options
+    }
+}
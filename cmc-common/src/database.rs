@@ -0,0 +1,74 @@
+//! Schema of the database, i.e. the migrations in `migrations`, which are embedded in the binaries
+//! so that they can bootstrap the database and check that its schema is the one they expect.
+
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::{ConnectOptions, Executor, PgConnection, PgPool};
+
+use crate::config::DbConfig;
+
+/// Migrations embedded in the binary, i.e. the schema it expects.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+/// Creates the database if missing and runs the pending embedded migrations. The whole bootstrap
+/// holds an advisory lock on the maintenance database (the target one may not exist yet), so
+/// concurrent migrators (e.g. several instances starting at once) run one at a time. Migrations
+/// applied by a newer binary are ignored.
+pub async fn migrate(config: &DbConfig) -> Result<(), MigrateError> {
+    let mut conn = config.without_db().connect().await?;
+    let lock = format!("migrate {}", config.database_name);
+
+    sqlx::query("SELECT pg_advisory_lock(hashtext($1));")
+        .bind(&lock)
+        .execute(&mut conn)
+        .await?;
+    let result = create_and_migrate(config, &mut conn).await;
+    sqlx::query("SELECT pg_advisory_unlock(hashtext($1));")
+        .bind(&lock)
+        .execute(&mut conn)
+        .await?;
+
+    result
+}
+
+async fn create_and_migrate(
+    config: &DbConfig,
+    conn: &mut PgConnection,
+) -> Result<(), MigrateError> {
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_database WHERE datname = $1);")
+            .bind(&config.database_name)
+            .fetch_one(&mut *conn)
+            .await?;
+    if !exists {
+        log::info!("Creating the database {}", config.database_name);
+        let name = config.database_name.replace('"', "\"\"");
+        conn.execute(format!(r#"CREATE DATABASE "{}";"#, name).as_str())
+            .await?;
+    }
+
+    let migrator = Migrator {
+        migrations: MIGRATOR.migrations.clone(),
+        ignore_missing: true,
+    };
+    migrator.run(&mut config.with_db().connect().await?).await
+}
+
+/// Returns the versions of the embedded migrations that have not been applied to the database yet,
+/// i.e. whether its schema is older than the one expected by the binary.
+pub async fn get_pending_migrations(pool: PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL;")
+        .fetch_one(&pool)
+        .await?;
+    let applied: Vec<i64> = if exists {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success;")
+            .fetch_all(&pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
@@ -0,0 +1,35 @@
+//! Deserializers shared by the models of the CoinMarketCap API.
+
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Deserializes an optional number as a `BigDecimal`, tolerating `null`, numbers in exponent
+/// notation and numeric strings. Unlike `Decimal`, which is limited to 28 significant digits, any
+/// real-world value (e.g. huge supplies or fully-diluted market caps) can be represented and then
+/// stored losslessly as a Postgres `NUMERIC`. Invalid values are logged and deserialized as `None`,
+/// so a single bad field doesn't fail the whole response. Use it along with `#[serde(default)]` to
+/// also tolerate missing fields.
+pub fn lenient_number<'de, D>(deserializer: D) -> Result<Option<BigDecimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let number = match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::String(number)) => number.trim().to_string(),
+        Some(other) => {
+            log::warn!("Ignoring the value `{}`: not a number", other);
+            return Ok(None);
+        }
+    };
+
+    match BigDecimal::from_str(&number) {
+        Ok(number) => Ok(Some(number)),
+        Err(_) => {
+            log::warn!("Ignoring the value `{}`: not a valid number", number);
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Pieces shared by `coin-market-cap`, which fills the database, and `crypto-forex`, which serves
+//! it: the schema (i.e. the migrations), the database settings and the deserialization of the
+//! numbers of the CoinMarketCap API.
+
+pub mod config;
+pub mod database;
+pub mod de;
@@ -0,0 +1,55 @@
+//! Settings of the mock server, given on the command line (see `cmc-mock --help`).
+
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Fixtures of the `coin-market-cap` tests, served by default.
+const DEFAULT_DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../coin-market-cap/tests/data");
+
+/// Local stand-in for the CoinMarketCap API, serving fixture files with configurable faults.
+#[derive(Debug, Clone, Parser)]
+#[clap(version, about)]
+pub struct MockConfig {
+    #[clap(long, default_value = "127.0.0.1")]
+    pub host: String,
+    /// Port to listen on (a random free port if 0).
+    #[clap(long, default_value = "4000")]
+    pub port: u16,
+    /// Directory of the fixture files.
+    #[clap(long, default_value = DEFAULT_DATA)]
+    pub data: PathBuf,
+    /// Response of `/v1/cryptocurrency/map`.
+    #[clap(long, default_value = "cryptocurrency_map_100_ranked.json")]
+    pub map: String,
+    /// Response of `/v1/cryptocurrency/listings/latest`, also used by `/v2/cryptocurrency/quotes/latest`.
+    #[clap(long, default_value = "cryptocurrency_listings_latest_100.json")]
+    pub listings: String,
+    /// Response of `/v2/cryptocurrency/quotes/historical`.
+    #[clap(long, default_value = "cryptocurrency_quotes_historical_3.json")]
+    pub quotes_historical: String,
+    /// Response of `/v2/cryptocurrency/info`.
+    #[clap(long, default_value = "cryptocurrency_info_3.json")]
+    pub info: String,
+    /// Response of `/v1/global-metrics/quotes/latest`.
+    #[clap(long, default_value = "global_metrics_quotes_latest.json")]
+    pub global_metrics: String,
+    /// Response of `/v1/key/info`.
+    #[clap(long, default_value = "key_info.json")]
+    pub key_info: String,
+    /// Expected value of the `X-CMC_PRO_API_KEY` header. If omitted, any key is accepted, but the
+    /// header is still required.
+    #[clap(long)]
+    pub api_key: Option<String>,
+    /// Milliseconds by which every response is delayed.
+    #[clap(long, default_value = "0")]
+    pub latency_ms: u64,
+    /// Fails every n-th authenticated request (never if 0).
+    #[clap(long, default_value = "0")]
+    pub error_every: u64,
+    /// HTTP status of the injected errors.
+    #[clap(long, default_value = "500")]
+    pub error_status: u16,
+    /// Maximum number of requests per minute, like the rate limit of a plan (unlimited if omitted).
+    #[clap(long)]
+    pub rate_limit: Option<u32>,
+}
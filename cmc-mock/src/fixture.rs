@@ -0,0 +1,52 @@
+//! Fixture files served by the mock server, i.e. responses of the CoinMarketCap API of which only
+//! the `data` is kept.
+
+use serde_json::{Map, Value};
+
+use crate::config::MockConfig;
+use crate::mock::MockError;
+
+pub struct Fixtures {
+    pub map: Vec<Value>,
+    pub listings: Vec<Value>,
+    /// Historical quotes by id.
+    pub quotes_historical: Map<String, Value>,
+    /// Metadata by id.
+    pub info: Map<String, Value>,
+    pub global_metrics: Value,
+    pub key_info: Value,
+}
+
+impl Fixtures {
+    pub fn load(config: &MockConfig) -> Result<Self, MockError> {
+        Ok(Self {
+            map: serde_json::from_value(load_data(config, &config.map)?)?,
+            listings: serde_json::from_value(load_data(config, &config.listings)?)?,
+            quotes_historical: serde_json::from_value(load_data(
+                config,
+                &config.quotes_historical,
+            )?)?,
+            info: serde_json::from_value(load_data(config, &config.info)?)?,
+            global_metrics: load_data(config, &config.global_metrics)?,
+            key_info: load_data(config, &config.key_info)?,
+        })
+    }
+
+    /// Returns the listing of the cryptocurrency with the given `symbol`, if any.
+    pub fn listing_by_symbol(&self, symbol: &str) -> Option<&Value> {
+        self.listings
+            .iter()
+            .find(|listing| listing["symbol"].as_str() == Some(symbol))
+    }
+}
+
+/// Returns the `data` of the response stored in the fixture file `name`.
+fn load_data(config: &MockConfig, name: &str) -> Result<Value, MockError> {
+    let contents = std::fs::read_to_string(config.data.join(name))?;
+    let mut response: Value = serde_json::from_str(&contents)?;
+
+    response
+        .get_mut("data")
+        .map(Value::take)
+        .ok_or_else(|| MockError::Fixture(name.to_string()))
+}
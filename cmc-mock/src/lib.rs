@@ -0,0 +1,4 @@
+pub mod config;
+pub mod fixture;
+pub mod mock;
+pub mod route;
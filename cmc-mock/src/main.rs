@@ -0,0 +1,28 @@
+use clap::Parser;
+use cmc_mock::config::MockConfig;
+use cmc_mock::mock::{MockError, MockServer};
+
+/// Entry point of the `cmc-mock` application, a local stand-in for the CoinMarketCap API that
+/// serves fixture files through the following endpoints:
+/// - `/v1/cryptocurrency/map`
+/// - `/v1/cryptocurrency/listings/latest`
+/// - `/v2/cryptocurrency/quotes/latest`
+/// - `/v2/cryptocurrency/quotes/historical`
+/// - `/v2/cryptocurrency/info`
+/// - `/v1/global-metrics/quotes/latest`
+/// - `/v1/key/info`
+///
+/// See `cmc-mock --help` for the fixtures and the faults that can be injected.
+#[actix_web::main]
+async fn main() -> Result<(), MockError> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let config = MockConfig::parse();
+    let server = MockServer::build(config)?;
+    log::info!(
+        "Serving the mock CoinMarketCap API on port {}",
+        server.port()
+    );
+    server.run().await?;
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use actix_web::{
+    dev::Server,
+    http::StatusCode,
+    middleware::Logger,
+    web::{self, Data},
+    App, HttpRequest, HttpResponse, HttpServer, ResponseError,
+};
+use chrono::prelude::*;
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::config::MockConfig;
+use crate::fixture::Fixtures;
+use crate::route::{
+    crypto_info, crypto_listing, crypto_map, crypto_quotes, global_metrics, key_info,
+};
+
+const API_KEY_HEADER: &str = "X-CMC_PRO_API_KEY";
+
+pub struct MockServer {
+    port: u16,
+    server: Server,
+}
+
+impl MockServer {
+    /// Builds the server once the fixture files are loaded.
+    pub fn build(config: MockConfig) -> Result<Self, MockError> {
+        let fixtures = Fixtures::load(&config)?;
+
+        let address = format!("{}:{}", config.host, config.port);
+        let listener = TcpListener::bind(&address)?;
+        let port = listener.local_addr().unwrap().port();
+        let server = setup(listener, State::new(config, fixtures))?;
+
+        Ok(Self { port, server })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub async fn run(self) -> Result<(), std::io::Error> {
+        self.server.await
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MockError {
+    #[error("Issues with I/O operations")]
+    Io(#[from] std::io::Error),
+    #[error("Issues parsing a fixture")]
+    Parse(#[from] serde_json::Error),
+    #[error("The fixture {0} has no `data`")]
+    Fixture(String),
+    #[error("API key missing.")]
+    ApiKeyMissing,
+    #[error("This API Key is invalid.")]
+    ApiKeyInvalid,
+    #[error(
+        "You've exceeded your API Key's HTTP request rate limit. Rate limits reset every minute."
+    )]
+    RateLimited,
+    #[error("An internal server error occurred.")]
+    Injected(u16),
+    #[error("{0}")]
+    BadRequest(String),
+}
+
+impl MockError {
+    /// Returns an error for an invalid `value` of the query parameter `name`.
+    pub fn invalid_value(name: &str, value: &str) -> Self {
+        MockError::BadRequest(format!("Invalid value for \"{}\": \"{}\"", name, value))
+    }
+
+    /// Internal error code of the CoinMarketCap API.
+    fn error_code(&self) -> u32 {
+        match self {
+            MockError::ApiKeyInvalid => 1001,
+            MockError::ApiKeyMissing => 1002,
+            MockError::RateLimited => 1008,
+            error => u32::from(error.status_code().as_u16()),
+        }
+    }
+}
+
+impl ResponseError for MockError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            MockError::ApiKeyMissing | MockError::ApiKeyInvalid => StatusCode::UNAUTHORIZED,
+            MockError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            MockError::Injected(status) => {
+                StatusCode::from_u16(*status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+            MockError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(json!({
+            "status": status(self.error_code(), Some(self.to_string()), 0),
+        }))
+    }
+}
+
+/// Shared state of the handlers.
+pub struct State {
+    pub config: MockConfig,
+    pub fixtures: Fixtures,
+    /// Number of authenticated requests so far.
+    requests: AtomicU64,
+    /// Start of the current rate limit window and number of requests made in it.
+    window: Mutex<(Instant, u32)>,
+}
+
+impl State {
+    pub fn new(config: MockConfig, fixtures: Fixtures) -> Self {
+        Self {
+            config,
+            fixtures,
+            requests: AtomicU64::new(0),
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Applies the configured faults to `request` before it's served, i.e. the latency, the check
+    /// of the API key, the rate limit and the injected errors (in this order).
+    pub async fn check(&self, request: &HttpRequest) -> Result<(), MockError> {
+        if self.config.latency_ms > 0 {
+            actix_web::rt::time::sleep(Duration::from_millis(self.config.latency_ms)).await;
+        }
+
+        let api_key = request
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|api_key| api_key.to_str().ok())
+            .filter(|api_key| !api_key.is_empty())
+            .ok_or(MockError::ApiKeyMissing)?;
+        if matches!(&self.config.api_key, Some(expected) if expected != api_key) {
+            return Err(MockError::ApiKeyInvalid);
+        }
+
+        if let Some(rate_limit) = self.config.rate_limit {
+            let mut window = self.window.lock().unwrap();
+            if window.0.elapsed() >= Duration::from_secs(60) {
+                *window = (Instant::now(), 0);
+            }
+            if window.1 >= rate_limit {
+                return Err(MockError::RateLimited);
+            }
+            window.1 += 1;
+        }
+
+        let requests = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        if self.config.error_every > 0 && requests.is_multiple_of(self.config.error_every) {
+            return Err(MockError::Injected(self.config.error_status));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a successful response with the given `data`, consuming `credit_count` call credits.
+pub fn respond(data: Value, credit_count: u32) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "data": data,
+        "status": status(0, None, credit_count),
+    }))
+}
+
+/// Returns a successful response with a page of `data` out of `total_count` items, consuming
+/// `credit_count` call credits.
+pub fn respond_page(data: Vec<Value>, total_count: usize, credit_count: u32) -> HttpResponse {
+    let mut status = status(0, None, credit_count);
+    status["total_count"] = total_count.into();
+
+    HttpResponse::Ok().json(json!({
+        "data": data,
+        "status": status,
+    }))
+}
+
+/// Returns the `status` object included in every response.
+fn status(error_code: u32, error_message: Option<String>, credit_count: u32) -> Value {
+    json!({
+        "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "error_code": error_code,
+        "error_message": error_message,
+        "elapsed": 0,
+        "credit_count": credit_count,
+        "notice": null,
+    })
+}
+
+fn setup(listener: TcpListener, state: State) -> Result<Server, std::io::Error> {
+    let state = Data::new(state);
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(Logger::default())
+            .route("/v1/cryptocurrency/map", web::get().to(crypto_map::map))
+            .route(
+                "/v1/cryptocurrency/listings/latest",
+                web::get().to(crypto_listing::listings),
+            )
+            .route(
+                "/v2/cryptocurrency/quotes/latest",
+                web::get().to(crypto_quotes::latest),
+            )
+            .route(
+                "/v2/cryptocurrency/quotes/historical",
+                web::get().to(crypto_quotes::historical),
+            )
+            .route("/v2/cryptocurrency/info", web::get().to(crypto_info::info))
+            .route(
+                "/v1/global-metrics/quotes/latest",
+                web::get().to(global_metrics::quotes_latest),
+            )
+            .route("/v1/key/info", web::get().to(key_info::key_info))
+            .app_data(state.clone())
+    })
+    .listen(listener)?
+    .run();
+
+    Ok(server)
+}
@@ -0,0 +1,31 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::{Map, Value};
+
+use super::{credits, Params};
+use crate::mock::{respond, MockError, State};
+
+/// Serves `/v2/cryptocurrency/info` by `id`.
+pub async fn info(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    let mut data = Map::new();
+    for id in query.ids()? {
+        let info = state
+            .fixtures
+            .info
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| MockError::invalid_value("id", &id))?;
+        data.insert(id, info);
+    }
+    if data.is_empty() {
+        return Err(MockError::BadRequest("\"id\" is required".to_string()));
+    }
+    let credit_count = credits(data.len(), 100);
+
+    Ok(respond(Value::Object(data), credit_count))
+}
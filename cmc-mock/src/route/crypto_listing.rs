@@ -0,0 +1,29 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::Value;
+
+use super::{convert, credits, Params};
+use crate::mock::{respond_page, MockError, State};
+
+/// Serves `/v1/cryptocurrency/listings/latest`, paginated by `start` and `limit` (100 by default)
+/// and quoted in every currency of `convert`.
+pub async fn listings(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    let rates = query.rates(&state.fixtures)?;
+    let data: Vec<Value> = query
+        .page(&state.fixtures.listings, 100)?
+        .iter()
+        .map(|listing| convert(listing, &rates))
+        .collect();
+    let credit_count = credits(data.len(), 200) * rates.len() as u32;
+
+    Ok(respond_page(
+        data,
+        state.fixtures.listings.len(),
+        credit_count,
+    ))
+}
@@ -0,0 +1,32 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::Value;
+
+use super::Params;
+use crate::mock::{respond_page, MockError, State};
+
+/// Serves `/v1/cryptocurrency/map`, optionally filtered by `symbol` and paginated by `start` and
+/// `limit` (the whole map by default).
+pub async fn map(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    let symbols = query.list("symbol");
+    let map: Vec<Value> = state
+        .fixtures
+        .map
+        .iter()
+        .filter(|data| {
+            symbols.is_empty()
+                || symbols
+                    .iter()
+                    .any(|symbol| data["symbol"].as_str() == Some(symbol))
+        })
+        .cloned()
+        .collect();
+    let data = query.page(&map, super::MAX_LIMIT)?.to_vec();
+
+    Ok(respond_page(data, map.len(), 1))
+}
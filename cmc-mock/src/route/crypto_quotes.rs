@@ -0,0 +1,82 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::{Map, Value};
+
+use super::{convert, credits, Params};
+use crate::mock::{respond, MockError, State};
+
+/// Serves `/v2/cryptocurrency/quotes/latest` from the listings, either by `id` (keyed by id) or by
+/// `symbol` (keyed by symbol, since symbols are not unique), quoted in every currency of `convert`.
+pub async fn latest(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    let rates = query.rates(&state.fixtures)?;
+    let listings = &state.fixtures.listings;
+    let mut data = Map::new();
+
+    let ids = query.ids()?;
+    let symbols = query.list("symbol");
+    if ids.is_empty() == symbols.is_empty() {
+        return Err(MockError::BadRequest(
+            "Exactly one of \"id\" or \"symbol\" is required".to_string(),
+        ));
+    }
+    for id in &ids {
+        let listing = listings
+            .iter()
+            .find(|listing| listing["id"].as_u64() == id.parse().ok())
+            .ok_or_else(|| MockError::invalid_value("id", id))?;
+        data.insert(id.clone(), convert(listing, &rates));
+    }
+    for symbol in &symbols {
+        let symbol = symbol.to_uppercase();
+        let matches: Vec<Value> = listings
+            .iter()
+            .filter(|listing| listing["symbol"].as_str() == Some(&symbol))
+            .map(|listing| convert(listing, &rates))
+            .collect();
+        data.insert(symbol, Value::from(matches));
+    }
+    let credit_count = credits(data.len(), 100) * rates.len() as u32;
+
+    Ok(respond(Value::Object(data), credit_count))
+}
+
+/// Serves `/v2/cryptocurrency/quotes/historical` by `id`, limited to the first `count` quotes (10 by
+/// default). Only USD quotes are available.
+pub async fn historical(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    if let Some(symbol) = query
+        .list("convert")
+        .into_iter()
+        .find(|symbol| !symbol.eq_ignore_ascii_case("USD"))
+    {
+        return Err(MockError::invalid_value("convert", &symbol));
+    }
+    let count = query.get::<usize>("count")?.unwrap_or(10);
+
+    let mut data = Map::new();
+    for id in query.ids()? {
+        let mut quotes = state
+            .fixtures
+            .quotes_historical
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| MockError::invalid_value("id", &id))?;
+        if let Some(quotes) = quotes["quotes"].as_array_mut() {
+            quotes.truncate(count);
+        }
+        data.insert(id, quotes);
+    }
+    let credit_count = credits(count, 100) * data.len() as u32;
+
+    Ok(respond(Value::Object(data), credit_count))
+}
@@ -0,0 +1,23 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::Params;
+use crate::mock::{respond, MockError, State};
+
+/// Serves `/v1/global-metrics/quotes/latest`, whose quotes are only available in USD.
+pub async fn quotes_latest(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    if let Some(symbol) = query
+        .list("convert")
+        .into_iter()
+        .find(|symbol| !symbol.eq_ignore_ascii_case("USD"))
+    {
+        return Err(MockError::invalid_value("convert", &symbol));
+    }
+
+    Ok(respond(state.fixtures.global_metrics.clone(), 1))
+}
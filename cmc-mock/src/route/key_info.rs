@@ -0,0 +1,13 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use crate::mock::{respond, MockError, State};
+
+/// Serves `/v1/key/info`, which doesn't consume any call credit.
+pub async fn key_info(
+    request: HttpRequest,
+    state: web::Data<State>,
+) -> Result<HttpResponse, MockError> {
+    state.check(&request).await?;
+
+    Ok(respond(state.fixtures.key_info.clone(), 0))
+}
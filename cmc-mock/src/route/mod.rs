@@ -0,0 +1,144 @@
+pub mod crypto_info;
+pub mod crypto_listing;
+pub mod crypto_map;
+pub mod crypto_quotes;
+pub mod global_metrics;
+pub mod key_info;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::fixture::Fixtures;
+use crate::mock::MockError;
+
+/// Maximum `limit` of the paginated endpoints.
+const MAX_LIMIT: u32 = 5000;
+
+/// Fields of a USD quote that are converted into other currencies (the rest are ratios or dates).
+const CONVERTED_FIELDS: [&str; 4] = [
+    "price",
+    "volume_24h",
+    "market_cap",
+    "fully_diluted_market_cap",
+];
+
+/// Query parameters of a request, parsed by each handler so that invalid values are reported like
+/// the CoinMarketCap API does.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Params(HashMap<String, String>);
+
+impl Params {
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, MockError> {
+        self.0
+            .get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| MockError::invalid_value(name, value))
+            })
+            .transpose()
+    }
+
+    /// Returns the comma-separated values of the parameter `name`, if any.
+    pub fn list(&self, name: &str) -> Vec<String> {
+        self.0
+            .get(name)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|item| item.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the ids of the parameter `id`.
+    pub fn ids(&self) -> Result<Vec<String>, MockError> {
+        self.list("id")
+            .into_iter()
+            .map(|id| match id.parse::<u32>() {
+                Ok(_) => Ok(id),
+                Err(_) => Err(MockError::invalid_value("id", &id)),
+            })
+            .collect()
+    }
+
+    /// Returns the page of `data` given by the 1-based `start` and `limit` (`default_limit` if
+    /// missing).
+    pub fn page<'a>(
+        &self,
+        data: &'a [Value],
+        default_limit: u32,
+    ) -> Result<&'a [Value], MockError> {
+        let start = self.get::<u32>("start")?.unwrap_or(1);
+        if start == 0 {
+            return Err(MockError::invalid_value("start", "0"));
+        }
+        let limit = self.get::<u32>("limit")?.unwrap_or(default_limit);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(MockError::invalid_value("limit", &limit.to_string()));
+        }
+
+        let start = (start as usize - 1).min(data.len());
+        let end = (start + limit as usize).min(data.len());
+        Ok(&data[start..end])
+    }
+
+    /// Returns the rates of the currencies in `convert` (`USD` by default), i.e. the units of each
+    /// currency per US dollar. Besides USD, any cryptocurrency of the listings is supported.
+    pub fn rates(&self, fixtures: &Fixtures) -> Result<Vec<(String, f64)>, MockError> {
+        let mut convert = self.list("convert");
+        if convert.is_empty() {
+            convert.push("USD".to_string());
+        }
+
+        convert
+            .into_iter()
+            .map(|symbol| {
+                let symbol = symbol.to_uppercase();
+                if symbol == "USD" {
+                    return Ok((symbol, 1.0));
+                }
+                fixtures
+                    .listing_by_symbol(&symbol)
+                    .and_then(|listing| listing["quote"]["USD"]["price"].as_f64())
+                    .filter(|price| *price > 0.0)
+                    .map(|price| (symbol.clone(), 1.0 / price))
+                    .ok_or_else(|| MockError::invalid_value("convert", &symbol))
+            })
+            .collect()
+    }
+}
+
+/// Returns `listing` quoted in each of the currencies of `rates`, derived from its USD quote.
+pub fn convert(listing: &Value, rates: &[(String, f64)]) -> Value {
+    let mut listing = listing.clone();
+    let usd = listing["quote"]["USD"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+
+    let quote: Map<String, Value> = rates
+        .iter()
+        .map(|(symbol, rate)| {
+            let mut quote = usd.clone();
+            for field in CONVERTED_FIELDS {
+                if let Some(value) = quote.get(field).and_then(Value::as_f64) {
+                    quote.insert(field.to_string(), (value * rate).into());
+                }
+            }
+            (symbol.clone(), Value::Object(quote))
+        })
+        .collect();
+    listing["quote"] = Value::Object(quote);
+
+    listing
+}
+
+/// Call credits consumed by a request of `count` items, charged per `per` items.
+pub fn credits(count: usize, per: usize) -> u32 {
+    count.max(1).div_ceil(per) as u32
+}
@@ -0,0 +1,138 @@
+use clap::Parser;
+use cmc_mock::config::MockConfig;
+use cmc_mock::mock::MockServer;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+const API_KEY: &str = "test-key";
+
+/// Spawns a mock server on a random port with the given extra arguments, returning its base URL.
+fn spawn_server(args: &[&str]) -> String {
+    let mut argv = vec!["cmc-mock", "--port", "0", "--api-key", API_KEY];
+    argv.extend_from_slice(args);
+    let server =
+        MockServer::build(MockConfig::parse_from(argv)).expect("Failed to build the server");
+    let port = server.port();
+    actix_rt::spawn(async move {
+        server.run().await.expect("Failed to run the server");
+    });
+
+    format!("http://127.0.0.1:{}", port)
+}
+
+async fn get(url: &str, api_key: Option<&str>) -> (u16, Value) {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(api_key) = api_key {
+        request = request.header("X-CMC_PRO_API_KEY", api_key);
+    }
+    let response = request.send().await.expect("Failed to send the request");
+
+    (
+        response.status().as_u16(),
+        response.json().await.expect("Failed to parse the response"),
+    )
+}
+
+#[actix_rt::test]
+async fn listings_honour_start_limit_and_convert() {
+    let base_url = spawn_server(&[]);
+
+    let url = format!(
+        "{}/v1/cryptocurrency/listings/latest?start=2&limit=3&convert=USD,BTC",
+        base_url
+    );
+    let (status, body) = get(&url, Some(API_KEY)).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["status"]["error_code"], 0);
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 3);
+    assert_eq!(data[0]["symbol"], "ETH");
+    let usd = data[0]["quote"]["USD"]["price"].as_f64().unwrap();
+    let btc = data[0]["quote"]["BTC"]["price"].as_f64().unwrap();
+    assert!(btc > 0.0 && btc < usd);
+
+    let url = format!("{}/v1/cryptocurrency/listings/latest?convert=XYZ", base_url);
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["status"]["error_code"], 400);
+}
+
+#[actix_rt::test]
+async fn map_quotes_and_info() {
+    let base_url = spawn_server(&[]);
+
+    let url = format!("{}/v1/cryptocurrency/map?symbol=BTC,ETH", base_url);
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    let url = format!("{}/v2/cryptocurrency/quotes/latest?id=1,1027", base_url);
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["1027"]["symbol"], "ETH");
+
+    let url = format!(
+        "{}/v2/cryptocurrency/quotes/historical?id=1&count=2",
+        base_url
+    );
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["1"]["quotes"].as_array().unwrap().len(), 2);
+
+    let url = format!("{}/v2/cryptocurrency/info?id=1,825", base_url);
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["825"]["symbol"], "USDT");
+
+    let url = format!("{}/v1/global-metrics/quotes/latest", base_url);
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["active_cryptocurrencies"], 6723);
+}
+
+#[actix_rt::test]
+async fn api_key_is_checked() {
+    let base_url = spawn_server(&[]);
+    let url = format!("{}/v1/key/info", base_url);
+
+    let (status, body) = get(&url, None).await;
+    assert_eq!(status, 401);
+    assert_eq!(body["status"]["error_code"], 1002);
+
+    let (status, body) = get(&url, Some("wrong-key")).await;
+    assert_eq!(status, 401);
+    assert_eq!(body["status"]["error_code"], 1001);
+}
+
+#[actix_rt::test]
+async fn rate_limit_is_enforced() {
+    let base_url = spawn_server(&["--rate-limit", "2"]);
+    let url = format!("{}/v1/key/info", base_url);
+
+    for _ in 0..2 {
+        assert_eq!(get(&url, Some(API_KEY)).await.0, 200);
+    }
+    let (status, body) = get(&url, Some(API_KEY)).await;
+    assert_eq!(status, 429);
+    assert_eq!(body["status"]["error_code"], 1008);
+}
+
+#[actix_rt::test]
+async fn errors_and_latency_are_injected() {
+    let base_url = spawn_server(&[
+        "--error-every",
+        "2",
+        "--error-status",
+        "503",
+        "--latency-ms",
+        "50",
+    ]);
+    let url = format!("{}/v1/key/info", base_url);
+
+    let started_at = Instant::now();
+    assert_eq!(get(&url, Some(API_KEY)).await.0, 200);
+    assert!(started_at.elapsed() >= Duration::from_millis(50));
+    assert_eq!(get(&url, Some(API_KEY)).await.0, 503);
+    assert_eq!(get(&url, Some(API_KEY)).await.0, 200);
+}
@@ -0,0 +1,127 @@
+//! Module that records the requests sent to the CoinMarketCap API along with their responses (i.e.
+//! cassettes), and replays them later without any network access nor call credit, e.g. to run the
+//! tests of the client offline. The mode is set in the `cassette` section of `coin_market`.
+//!
+//! Every cassette is a JSON file in the cassette directory, named after the endpoint and a hash of
+//! the query parameters, so a request is always served the same response. Neither the base URL nor
+//! the API key are part of a cassette.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::coin_market::CmcError;
+use crate::configuration::CoinMarketConfig;
+
+/// Whether requests are sent to the API, recorded or replayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CassetteMode {
+    /// Requests are sent to the API.
+    #[default]
+    Off,
+    /// Requests are sent to the API and recorded, overwriting the existing cassettes.
+    Record,
+    /// Requests are served from the recorded cassettes, failing if missing.
+    Replay,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cassette {
+    pub request: Request,
+    pub response: Response,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub endpoint: String,
+    /// Query parameters, sorted by name.
+    pub params: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    /// HTTP status code.
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Request {
+    pub fn new(endpoint: &str, params: &[(&str, String)]) -> Self {
+        let mut params: Vec<_> = params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        params.sort();
+
+        Self {
+            endpoint: endpoint.to_string(),
+            params,
+        }
+    }
+
+    /// Returns the path of the cassette of this request in `dir`, e.g.
+    /// `v1_cryptocurrency_map-a3f1c9e07b2d4e58.json`.
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        let query: Vec<_> = self
+            .params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        let hash = fnv1a(&format!("{}?{}", self.endpoint, query.join("&")));
+        let endpoint = self.endpoint.trim_start_matches('/').replace('/', "_");
+
+        dir.join(format!("{}-{:016x}.json", endpoint, hash))
+    }
+}
+
+/// 64-bit FNV-1a hash, which (unlike the hasher of the standard library) is stable across
+/// platforms and releases of Rust.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Sends a `GET` request to `endpoint` with the given query parameters, unless replayed from a
+/// cassette, and returns the status code and the JSON body of the response.
+pub async fn send(
+    config: &CoinMarketConfig,
+    endpoint: &str,
+    params: &[(&str, String)],
+) -> Result<Response, CmcError> {
+    let request = Request::new(endpoint, params);
+    let path = request.path(&config.cassette.dir);
+
+    if config.cassette.mode == CassetteMode::Replay {
+        let contents =
+            std::fs::read_to_string(&path).map_err(|_| CmcError::MissingCassette(path.clone()))?;
+        let cassette: Cassette = serde_json::from_str(&contents)?;
+        return Ok(cassette.response);
+    }
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(config.base_url.clone() + endpoint)
+        .header("X-CMC_PRO_API_KEY", &config.api_key)
+        .query(params)
+        .send()
+        .await?;
+    // Errors of proxies and load balancers are not always JSON, so their body is kept as a string.
+    let status = response.status().as_u16();
+    let text = response.text().await?;
+    let response = Response {
+        status,
+        body: serde_json::from_str(&text).unwrap_or(Value::String(text)),
+    };
+
+    if config.cassette.mode == CassetteMode::Record {
+        let cassette = Cassette { request, response };
+        std::fs::create_dir_all(&config.cassette.dir)?;
+        std::fs::write(&path, serde_json::to_string_pretty(&cassette)?)?;
+        log::debug!("Recorded cassette {}", path.display());
+        return Ok(cassette.response);
+    }
+
+    Ok(response)
+}
@@ -2,18 +2,36 @@
 //! It contains the following submodules:
 //! - `map` that consumes the endpoint `/v1/cryptocurrency/map`  
 //! - `listings/latest` that consumes the endpoint `/v1/cryptocurrency/listings/latest`
+//! - `quotes_historical` that consumes the endpoint `/v2/cryptocurrency/quotes/historical`
+//! - `ohlcv_historical` that consumes the endpoint `/v2/cryptocurrency/ohlcv/historical`
+//! - `categories` that consumes the endpoint `/v1/cryptocurrency/categories`
+//! - `category` that consumes the endpoint `/v1/cryptocurrency/category`
+//! - `trending` that consumes the endpoints `/v1/cryptocurrency/trending/*`
+//! - `key_info` that consumes the endpoint `/v1/key/info`
+//! - `info` that consumes the endpoint `/v2/cryptocurrency/info`
 //!
 //! **Remark:** Many cryptocurrencies have the same symbol, for example, there are currently three
 //! cryptocurrencies that commonly refer to themselves by the symbol `HOT`. Moreover, cryptocurrency
 //! symbols also often change with cryptocurrency rebrands.
 
-use chrono::prelude::*;
-use rust_decimal::prelude::*;
-use serde::Deserialize;
+use bigdecimal::BigDecimal;
+use chrono::{prelude::*, Duration};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::Value;
 use sqlx::PgPool;
+use std::collections::BTreeSet;
+use std::path::Path;
 use thiserror::Error;
 
-use crate::{configuration, database};
+pub use cmc_common::de::lenient_number;
+
+use crate::cassette;
+use crate::configuration::{self, CoinMarketConfig, Configuration};
+use crate::database;
+use crate::import::{self, Dump, ImportSummary};
+use crate::retention::{self, RetentionSummary};
+use crate::schema_drift;
+use crate::validation::{self, ValidationAction};
 
 #[derive(Debug, Deserialize)]
 pub struct Platform {
@@ -30,47 +48,341 @@ pub struct Usd {
     pub usd: Changes,
 }
 
+/// Latest market data of a cryptocurrency. Every numeric field may be `null` (or missing) for newly
+/// listed or untracked cryptocurrencies, in which case it is `None`.
 #[derive(Debug, Deserialize)]
 pub struct Changes {
     /// Latest average trade price across markets.
-    pub price: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub price: Option<BigDecimal>,
     /// A measure of how much of a cryptocurrency was traded in the last 24 hours.
-    pub volume_24h: Decimal,
-    pub volume_change_24h: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub volume_24h: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub volume_change_24h: Option<BigDecimal>,
     /// 1 hour trading price percentage change for each currency.
-    pub percent_change_1h: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub percent_change_1h: Option<BigDecimal>,
     /// 24 hour trading price percentage change for each currency.
-    pub percent_change_24h: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub percent_change_24h: Option<BigDecimal>,
     /// 7 day trading price percentage change for each currency.
-    pub percent_change_7d: Decimal,
-    pub percent_change_30d: Decimal,
-    pub percent_change_60d: Decimal,
-    pub percent_change_90d: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub percent_change_7d: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub percent_change_30d: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub percent_change_60d: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub percent_change_90d: Option<BigDecimal>,
     /// The total market value of a cryptocurrency's circulating supply. It is analogous to the
     /// free-float capitalization in the stock market.
     ///
     /// `Market Cap = Current Price x Circulating Supply`
     ///
     /// (see [details](https://coinmarketcap.com/methodology/))
-    pub market_cap: Decimal,
-    pub market_cap_dominance: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub market_cap: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub market_cap_dominance: Option<BigDecimal>,
     /// The market cap if the max supply was in circulation.
     ///
     /// Fully-diluted market cap `(FDMC) = price x max supply`. If max supply is null, `FDMC =
     /// price x total supply`. If max supply and total supply are infinite or not available,
     /// fully-diluted market cap shows `- -`.
-    pub fully_diluted_market_cap: Decimal,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub fully_diluted_market_cap: Option<BigDecimal>,
     pub last_updated: DateTime<Utc>,
 }
 
+impl Changes {
+    /// Returns the names of the fields that are missing (i.e. `null`, absent or invalid).
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let fields = [
+            ("price", &self.price),
+            ("volume_24h", &self.volume_24h),
+            ("volume_change_24h", &self.volume_change_24h),
+            ("percent_change_1h", &self.percent_change_1h),
+            ("percent_change_24h", &self.percent_change_24h),
+            ("percent_change_7d", &self.percent_change_7d),
+            ("percent_change_30d", &self.percent_change_30d),
+            ("percent_change_60d", &self.percent_change_60d),
+            ("percent_change_90d", &self.percent_change_90d),
+            ("market_cap", &self.market_cap),
+            ("market_cap_dominance", &self.market_cap_dominance),
+            ("fully_diluted_market_cap", &self.fully_diluted_market_cap),
+        ];
+
+        fields
+            .iter()
+            .filter(|(_, value)| value.is_none())
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+/// Records of a response deserialized one at a time, so that a malformed record (e.g. with a `null`
+/// quote or without `last_updated`) is logged and skipped instead of failing the whole response.
+///
+/// **Remark:** Records are only skipped reliably when deserialized from a `serde_json::Value`, as
+/// the responses of the API are (see `send_request`). Parsed straight from a string, a record
+/// malformed halfway still fails the whole response.
+#[derive(Debug)]
+pub struct Records<T> {
+    pub valid: Vec<T>,
+    /// Number of malformed records skipped.
+    pub skipped: u32,
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Records<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RecordsVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> serde::de::Visitor<'de> for RecordsVisitor<T> {
+            type Value = Records<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a list of records")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut records = Records {
+                    valid: Vec::new(),
+                    skipped: 0,
+                };
+                while let Some(Record(record)) = seq.next_element()? {
+                    match record {
+                        Some(record) => records.valid.push(record),
+                        None => records.skipped += 1,
+                    }
+                }
+
+                Ok(records)
+            }
+        }
+
+        deserializer.deserialize_seq(RecordsVisitor(std::marker::PhantomData))
+    }
+}
+
+/// A record of `Records`, which is `None` if malformed.
+struct Record<T>(Option<T>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Record<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match T::deserialize(deserializer) {
+            Ok(record) => Ok(Record(Some(record))),
+            Err(error) => {
+                log::warn!("Skipping a malformed record: {}", error);
+                Ok(Record(None))
+            }
+        }
+    }
+}
+
+/// Deserializes tags given either as slugs (e.g. by the listings) or as objects with a `slug` (e.g.
+/// by the v2 quotes).
+fn tag_slugs<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tags = match Option::<Vec<Value>>::deserialize(deserializer)? {
+        Some(tags) => tags,
+        None => return Ok(None),
+    };
+
+    tags.into_iter()
+        .map(|tag| match tag {
+            Value::String(slug) => Ok(slug),
+            Value::Object(mut tag) => match tag.remove("slug") {
+                Some(Value::String(slug)) => Ok(slug),
+                _ => Err(serde::de::Error::custom("tag without slug")),
+            },
+            other => Err(serde::de::Error::custom(format!("invalid tag `{}`", other))),
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Status object returned by the endpoints that don't report a `total_count`.
+#[allow(unused)]
+#[derive(Debug, Deserialize)]
+pub struct Status {
+    timestamp: DateTime<Utc>,
+    error_code: u32,
+    error_message: Option<String>,
+    elapsed: u32,
+    /// Number of call credits consumed by the request.
+    pub credit_count: u32,
+    notice: Option<String>,
+}
+
 #[derive(Error, Debug)]
 pub enum CmcError {
     #[error("Issues loading configuration")]
     LoadConfig(#[from] config::ConfigError),
+    #[error("Issues with I/O operations")]
+    Io(#[from] std::io::Error),
     #[error("Issues during the request to the server")]
     Request(#[from] reqwest::Error),
+    #[error("No cassette recorded for the request at {0}")]
+    MissingCassette(std::path::PathBuf),
+    #[error("Issues parsing the response of the server")]
+    Parse(#[from] serde_json::Error),
     #[error("Issues querying the database")]
     DbQuery(#[from] sqlx::Error),
+    #[error("Issues migrating the database")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error("The database schema is outdated, pending migrations: {0:?}")]
+    OutdatedSchema(Vec<i64>),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Not enough call credits: {required} required, {remaining} left this month")]
+    InsufficientCredits { required: u32, remaining: u32 },
+    #[error("The server replied with error {error_code}: {error_message}")]
+    Api {
+        error_code: u32,
+        error_message: String,
+    },
+}
+
+/// Class of a `CmcError`, which decides how the daemon reacts to it (see `poller`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Network failures and server errors, likely to succeed on retry.
+    Transient,
+    /// Too many requests or credits consumed, to retry once the window resets.
+    RateLimited,
+    /// Invalid, disabled or insufficient API key, which requires an operator.
+    Auth,
+    /// A response that cannot be parsed, to skip until the client is updated.
+    Schema,
+    /// Connection or query failures of the database.
+    Database,
+    /// Anything else, e.g. an invalid configuration or an outdated schema.
+    Fatal,
+}
+
+impl CmcError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            CmcError::Request(error) if error.is_decode() => ErrorClass::Schema,
+            CmcError::Request(error) if error.is_builder() => ErrorClass::Fatal,
+            CmcError::Request(_) => ErrorClass::Transient,
+            CmcError::Parse(_) => ErrorClass::Schema,
+            CmcError::DbQuery(error) => match error {
+                sqlx::Error::Configuration(_)
+                | sqlx::Error::ColumnNotFound(_)
+                | sqlx::Error::ColumnIndexOutOfBounds { .. }
+                | sqlx::Error::ColumnDecode { .. }
+                | sqlx::Error::Decode(_)
+                | sqlx::Error::TypeNotFound { .. } => ErrorClass::Fatal,
+                _ => ErrorClass::Database,
+            },
+            // Codes of the CoinMarketCap API, or HTTP statuses when the body is missing.
+            CmcError::Api { error_code, .. } => match error_code {
+                1001..=1007 | 401 | 402 | 403 => ErrorClass::Auth,
+                1008..=1011 | 429 => ErrorClass::RateLimited,
+                400 | 404 => ErrorClass::Fatal,
+                _ => ErrorClass::Transient,
+            },
+            CmcError::InsufficientCredits { .. } => ErrorClass::RateLimited,
+            _ => ErrorClass::Fatal,
+        }
+    }
+}
+
+/// Body returned by the server when a request fails.
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    status: ErrorStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorStatus {
+    error_code: u32,
+    error_message: Option<String>,
+}
+
+/// Settings and database pool shared by the requests to the CoinMarketCap API, e.g. those of an
+/// `App`.
+#[derive(Debug, Clone, Copy)]
+pub struct Client<'a> {
+    config: &'a CoinMarketConfig,
+    pool: &'a PgPool,
+}
+
+impl<'a> Client<'a> {
+    pub fn new(config: &'a CoinMarketConfig, pool: &'a PgPool) -> Self {
+        Self { config, pool }
+    }
+}
+
+/// Sends a `GET` request to `endpoint` of the CoinMarketCap API with the given query parameters
+/// and deserializes the JSON body of the response, which may be recorded or replayed instead (see
+/// `cassette`). If enabled in the configuration, the schema drift of the response (see
+/// `schema_drift`) is recorded as well.
+async fn send_request<T: DeserializeOwned>(
+    client: &Client<'_>,
+    endpoint: &str,
+    params: &[(&str, String)],
+) -> Result<T, CmcError> {
+    let config = client.config;
+
+    // Pull new data from the server (or a cassette)
+    let response = cassette::send(config, endpoint, params).await?;
+
+    if !(200..300).contains(&response.status) {
+        // Without the usual body, the HTTP status stands for the error code.
+        return Err(
+            match serde_json::from_value::<ErrorResponse>(response.body) {
+                Ok(error) => CmcError::Api {
+                    error_code: error.status.error_code,
+                    error_message: error.status.error_message.unwrap_or_default(),
+                },
+                Err(_) => CmcError::Api {
+                    error_code: u32::from(response.status),
+                    error_message: format!("HTTP status {}", response.status),
+                },
+            },
+        );
+    }
+
+    if !config.detect_schema_drift {
+        return Ok(serde_json::from_value(response.body)?);
+    }
+
+    let (data, drift) = schema_drift::deserialize(&response.body)?;
+    if !drift.is_empty() {
+        // Failing to record the drift must not fail the request.
+        match database::update_schema_drift(endpoint, &drift, Utc::now(), client.pool.clone()).await
+        {
+            Ok(new) => {
+                for path in &new.unknown {
+                    log::warn!("Unknown field `{}` in the response of `{}`", path, endpoint);
+                }
+                for path in &new.missing {
+                    log::warn!("Missing field `{}` in the response of `{}`", path, endpoint);
+                }
+            }
+            Err(error) => log::error!(
+                "Failed to record the schema drift of `{}`: {}",
+                endpoint,
+                error
+            ),
+        }
+    }
+
+    Ok(data)
 }
 
 /// Module that consumes the endpoint `/v1/cryptocurrency/map`. The latter returns a mapping of all
@@ -80,8 +392,7 @@ pub enum CmcError {
 /// supported exchanges. You may receive a map of all inactive cryptocurrencies by passing
 /// `listing_status=inactive`.
 pub mod map {
-    use super::{CmcError, Platform};
-    use crate::configuration;
+    use super::{send_request, Client, CmcError, Platform};
     use chrono::prelude::*;
     use serde::{
         self,
@@ -98,72 +409,201 @@ pub mod map {
     #[allow(unused)]
     #[derive(Debug, Deserialize)]
     pub struct Status {
-        timestamp: DateTime<Utc>,
+        /// When the response was generated, which dates the names and symbols of the map.
+        pub timestamp: DateTime<Utc>,
         error_code: u32,
         error_message: Option<String>,
         elapsed: u32,
-        credit_count: u32,
+        /// Number of call credits consumed by the request.
+        pub credit_count: u32,
         notice: Option<u32>,
     }
 
+    /// Fields marked as optional are either not set for inactive or untracked cryptocurrencies, or
+    /// left out by the `aux` selection.
     #[derive(Debug, Deserialize)]
     pub struct Data {
         pub id: u32,
         pub name: String,
         pub symbol: String,
         pub slug: String,
-        pub rank: u32,
-        #[serde(deserialize_with = "bool_from_int")]
-        pub is_active: bool,
-        pub first_historical_data: DateTime<Utc>,
-        pub last_historical_data: DateTime<Utc>,
+        pub rank: Option<u32>,
+        #[serde(default, deserialize_with = "bool_from_int")]
+        pub is_active: Option<bool>,
+        /// Listing status, i.e. `active`, `inactive` or `untracked` (requires `aux=status`).
+        pub status: Option<String>,
+        pub first_historical_data: Option<DateTime<Utc>>,
+        pub last_historical_data: Option<DateTime<Utc>>,
         pub platform: Option<Platform>,
     }
 
-    fn bool_from_int<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    fn bool_from_int<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        match u8::deserialize(deserializer)? {
-            0 => Ok(false),
-            1 => Ok(true),
-            other => Err(de::Error::invalid_value(
+        match Option::<u8>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(0) => Ok(Some(false)),
+            Some(1) => Ok(Some(true)),
+            Some(other) => Err(de::Error::invalid_value(
                 Unexpected::Unsigned(other as u64),
                 &"zero or one",
             )),
         }
     }
 
+    /// Supplemental fields that can be requested through `aux`.
+    pub const AUX_FIELDS: [&str; 5] = [
+        "platform",
+        "first_historical_data",
+        "last_historical_data",
+        "is_active",
+        "status",
+    ];
+
+    /// Listing status of the cryptocurrencies to map.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ListingStatus {
+        /// Cryptocurrencies that have actively tracked markets on supported exchanges.
+        Active,
+        /// Cryptocurrencies that are no longer actively tracked (e.g. delisted).
+        Inactive,
+        /// Cryptocurrencies listed on CoinMarketCap but not yet tracked.
+        Untracked,
+    }
+
+    impl ListingStatus {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                ListingStatus::Active => "active",
+                ListingStatus::Inactive => "inactive",
+                ListingStatus::Untracked => "untracked",
+            }
+        }
+    }
+
+    impl std::str::FromStr for ListingStatus {
+        type Err = CmcError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "active" => Ok(ListingStatus::Active),
+                "inactive" => Ok(ListingStatus::Inactive),
+                "untracked" => Ok(ListingStatus::Untracked),
+                other => Err(CmcError::InvalidArgument(format!(
+                    "`{}` is not a listing status, use `active`, `inactive` or `untracked`",
+                    other
+                ))),
+            }
+        }
+    }
+
+    /// Parameters of a request to the endpoint `/v1/cryptocurrency/map`. Unset parameters are left
+    /// to the server defaults.
+    #[derive(Debug, Clone, Default)]
+    pub struct MapQuery {
+        start: Option<u32>,
+        limit: Option<u32>,
+        sort: Option<String>,
+        listing_status: Vec<ListingStatus>,
+        symbol: Vec<String>,
+        aux: Vec<String>,
+    }
+
+    impl MapQuery {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Offsets the start (1-based index) of the paginated list of items to return.
+        pub fn start(mut self, start: u32) -> Self {
+            self.start = Some(start);
+            self
+        }
+
+        /// Number of results to return (up to 5000).
+        pub fn limit(mut self, limit: u32) -> Self {
+            self.limit = Some(limit);
+            self
+        }
+
+        /// Sorts the results either by `id` (the default) or `cmc_rank`.
+        pub fn sort(mut self, sort: &str) -> Self {
+            self.sort = Some(sort.to_string());
+            self
+        }
+
+        /// Only returns cryptocurrencies with one of these listing statuses (`active` by default).
+        pub fn listing_status(mut self, listing_status: &[ListingStatus]) -> Self {
+            self.listing_status = listing_status.to_vec();
+            self
+        }
+
+        /// Only returns cryptocurrencies with one of these symbols.
+        pub fn symbol(mut self, symbol: &[&str]) -> Self {
+            self.symbol = symbol.iter().map(|symbol| symbol.to_string()).collect();
+            self
+        }
+
+        /// Selects the supplemental fields to return among `AUX_FIELDS`.
+        pub fn aux(mut self, aux: &[&str]) -> Self {
+            self.aux = aux.iter().map(|field| field.to_string()).collect();
+            self
+        }
+
+        fn params(&self) -> Vec<(&'static str, String)> {
+            let mut params = Vec::new();
+
+            if let Some(start) = self.start {
+                params.push(("start", start.to_string()));
+            }
+            if let Some(limit) = self.limit {
+                params.push(("limit", limit.to_string()));
+            }
+            if let Some(sort) = &self.sort {
+                params.push(("sort", sort.clone()));
+            }
+            if !self.listing_status.is_empty() {
+                let listing_status: Vec<_> = self
+                    .listing_status
+                    .iter()
+                    .map(ListingStatus::as_str)
+                    .collect();
+                params.push(("listing_status", listing_status.join(",")));
+            }
+            if !self.symbol.is_empty() {
+                params.push(("symbol", self.symbol.join(",")));
+            }
+            if !self.aux.is_empty() {
+                params.push(("aux", self.aux.join(",")));
+            }
+
+            params
+        }
+    }
+
     /// Makes a request to the endpoint `/v1/cryptocurrency/map` of the CoinMarketCap API.
     /// Returns a mapping of all cryptocurrencies to unique CoinMarketCap `id`s.
     /// By default this endpoint returns cryptocurrencies that have actively tracked markets on
     /// supported exchanges. You may receive a map of all inactive cryptocurrencies by passing
     /// `listing_status=inactive`.
     pub async fn request_crypto_map(
+        client: &Client<'_>,
         start: u32,
         limit: u32,
         sort: &str,
     ) -> Result<Response, CmcError> {
-        let config = configuration::load_config()?;
-
-        // Pull new data from the server
-        let client = reqwest::Client::new();
-        let params = [
-            ("start", start.to_string()),
-            ("limit", limit.to_string()),
-            ("sort", sort.to_string()),
-        ];
-
-        let response: Response = client
-            .get(config.coin_market.base_url + "/v1/cryptocurrency/map")
-            .header("X-CMC_PRO_API_KEY", config.coin_market.api_key)
-            .query(&params)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let query = MapQuery::new().start(start).limit(limit).sort(sort);
+        request_crypto_map_with(client, &query).await
+    }
 
-        Ok(response)
+    /// Makes a request to the endpoint `/v1/cryptocurrency/map` of the CoinMarketCap API with all
+    /// the parameters in `query`.
+    pub async fn request_crypto_map_with(
+        client: &Client<'_>,
+        query: &MapQuery,
+    ) -> Result<Response, CmcError> {
+        send_request(client, "/v1/cryptocurrency/map", &query.params()).await
     }
 }
 
@@ -172,17 +612,41 @@ pub mod map {
 /// sort returns cryptocurrency in order of CoinMarketCap's market cap rank but you may configure
 /// this call to order by another market ranking field.
 pub mod listing {
-    use crate::configuration;
+    use bigdecimal::BigDecimal;
     use chrono::prelude::*;
     use rust_decimal::Decimal;
     use serde::Deserialize;
 
-    use super::{CmcError, Platform, Usd};
+    use super::{
+        lenient_number, send_request, tag_slugs, Client, CmcError, Platform, Records, Usd,
+    };
+
+    pub const ENDPOINT: &str = "/v1/cryptocurrency/listings/latest";
 
     #[derive(Debug, Deserialize)]
+    #[serde(from = "Page")]
     pub struct Response {
         pub data: Vec<Data>,
         pub status: Status,
+        /// Number of malformed records left out of `data`.
+        pub skipped: u32,
+    }
+
+    /// Response as sent by the server, whose records are deserialized one at a time.
+    #[derive(Deserialize)]
+    struct Page {
+        data: Records<Data>,
+        status: Status,
+    }
+
+    impl From<Page> for Response {
+        fn from(page: Page) -> Self {
+            Self {
+                data: page.data.valid,
+                status: page.status,
+                skipped: page.data.skipped,
+            }
+        }
     }
 
     #[allow(unused)]
@@ -192,11 +656,13 @@ pub mod listing {
         error_code: u32,
         error_message: Option<String>,
         elapsed: u32,
-        credit_count: u32,
+        /// Number of call credits consumed by the request.
+        pub credit_count: u32,
         notice: Option<u32>,
         total_count: u32,
     }
 
+    /// Fields marked as optional may be left out by the `aux` selection.
     #[derive(Debug, Deserialize)]
     pub struct Data {
         /// The CoinMarketCap's `id`.
@@ -209,86 +675,1969 @@ pub mod listing {
         pub symbol: String,
         pub slug: String,
         /// Number of market pairs across all exchanges trading each currency.
-        pub num_market_pairs: u32,
-        pub date_added: DateTime<Utc>,
-        pub tags: Vec<String>,
+        pub num_market_pairs: Option<u32>,
+        pub date_added: Option<DateTime<Utc>>,
+        #[serde(default, deserialize_with = "tag_slugs")]
+        pub tags: Option<Vec<String>>,
         /// Approximation of the maximum amount of coins that will ever exist in the lifetime
         /// of the currency.
-        pub max_supply: Option<Decimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub max_supply: Option<BigDecimal>,
         /// The amount of coins that are circulating in the market and are in public hands. It is
         /// analogous to the flowing shares in the stock market.
-        pub circulating_supply: Decimal,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub circulating_supply: Option<BigDecimal>,
         /// Approximate total amount of coins in existence right now (minus any coins that have been
         /// verifiably burned).
-        pub total_supply: Decimal,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub total_supply: Option<BigDecimal>,
         pub platform: Option<Platform>,
         /// CoinMarketCap's market cap rank as outlined in [their methodology](https://coinmarketcap.com/methodology/).
         /// Cryptocurrencies are listed by `cmc_rank` by default.
-        pub cmc_rank: u32,
+        pub cmc_rank: Option<u32>,
         pub last_updated: DateTime<Utc>,
         pub quote: Usd,
     }
 
+    /// Fields the listings can be sorted by.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Sort {
+        Name,
+        Symbol,
+        DateAdded,
+        MarketCap,
+        MarketCapStrict,
+        Price,
+        CirculatingSupply,
+        TotalSupply,
+        MaxSupply,
+        NumMarketPairs,
+        Volume24h,
+        PercentChange1h,
+        PercentChange24h,
+        PercentChange7d,
+        MarketCapByTotalSupplyStrict,
+        Volume7d,
+        Volume30d,
+    }
+
+    impl Sort {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Sort::Name => "name",
+                Sort::Symbol => "symbol",
+                Sort::DateAdded => "date_added",
+                Sort::MarketCap => "market_cap",
+                Sort::MarketCapStrict => "market_cap_strict",
+                Sort::Price => "price",
+                Sort::CirculatingSupply => "circulating_supply",
+                Sort::TotalSupply => "total_supply",
+                Sort::MaxSupply => "max_supply",
+                Sort::NumMarketPairs => "num_market_pairs",
+                Sort::Volume24h => "volume_24h",
+                Sort::PercentChange1h => "percent_change_1h",
+                Sort::PercentChange24h => "percent_change_24h",
+                Sort::PercentChange7d => "percent_change_7d",
+                Sort::MarketCapByTotalSupplyStrict => "market_cap_by_total_supply_strict",
+                Sort::Volume7d => "volume_7d",
+                Sort::Volume30d => "volume_30d",
+            }
+        }
+    }
+
+    impl std::str::FromStr for Sort {
+        type Err = CmcError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            const SORTS: [Sort; 17] = [
+                Sort::Name,
+                Sort::Symbol,
+                Sort::DateAdded,
+                Sort::MarketCap,
+                Sort::MarketCapStrict,
+                Sort::Price,
+                Sort::CirculatingSupply,
+                Sort::TotalSupply,
+                Sort::MaxSupply,
+                Sort::NumMarketPairs,
+                Sort::Volume24h,
+                Sort::PercentChange1h,
+                Sort::PercentChange24h,
+                Sort::PercentChange7d,
+                Sort::MarketCapByTotalSupplyStrict,
+                Sort::Volume7d,
+                Sort::Volume30d,
+            ];
+
+            SORTS
+                .iter()
+                .find(|sort| sort.as_str() == s)
+                .copied()
+                .ok_or_else(|| CmcError::InvalidArgument(format!("`{}` is not a listing sort", s)))
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SortDir {
+        Asc,
+        Desc,
+    }
+
+    impl SortDir {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                SortDir::Asc => "asc",
+                SortDir::Desc => "desc",
+            }
+        }
+    }
+
+    impl std::str::FromStr for SortDir {
+        type Err = CmcError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "asc" => Ok(SortDir::Asc),
+                "desc" => Ok(SortDir::Desc),
+                other => Err(CmcError::InvalidArgument(format!(
+                    "`{}` is not a sort direction, use `asc` or `desc`",
+                    other
+                ))),
+            }
+        }
+    }
+
+    /// Type of cryptocurrency to include in the listings.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CryptocurrencyType {
+        All,
+        Coins,
+        Tokens,
+    }
+
+    impl CryptocurrencyType {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                CryptocurrencyType::All => "all",
+                CryptocurrencyType::Coins => "coins",
+                CryptocurrencyType::Tokens => "tokens",
+            }
+        }
+    }
+
+    impl std::str::FromStr for CryptocurrencyType {
+        type Err = CmcError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "all" => Ok(CryptocurrencyType::All),
+                "coins" => Ok(CryptocurrencyType::Coins),
+                "tokens" => Ok(CryptocurrencyType::Tokens),
+                other => Err(CmcError::InvalidArgument(format!(
+                    "`{}` is not a cryptocurrency type, use `all`, `coins` or `tokens`",
+                    other
+                ))),
+            }
+        }
+    }
+
+    /// Supplemental fields that can be requested through `aux`.
+    pub const AUX_FIELDS: [&str; 15] = [
+        "num_market_pairs",
+        "cmc_rank",
+        "date_added",
+        "tags",
+        "platform",
+        "max_supply",
+        "circulating_supply",
+        "total_supply",
+        "market_cap_by_total_supply",
+        "volume_24h_reported",
+        "volume_7d",
+        "volume_7d_reported",
+        "volume_30d",
+        "volume_30d_reported",
+        "is_market_cap_included_in_calc",
+    ];
+
+    /// Parameters of a request to the endpoint `/v1/cryptocurrency/listings/latest`, which are
+    /// validated before sending the request. Unset parameters are left to the server defaults.
+    ///
+    /// For instance, the tokens with at least 1M USD of volume over the last 24 hours are requested
+    /// with
+    /// ```
+    /// # use coin_market_cap::coin_market::listing::{CryptocurrencyType, ListingQuery};
+    /// # use rust_decimal::Decimal;
+    /// let query = ListingQuery::new()
+    ///     .cryptocurrency_type(CryptocurrencyType::Tokens)
+    ///     .volume_24h_min(Decimal::new(1_000_000, 0));
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct ListingQuery {
+        start: Option<u32>,
+        limit: Option<u32>,
+        convert: Option<String>,
+        sort: Option<Sort>,
+        sort_dir: Option<SortDir>,
+        cryptocurrency_type: Option<CryptocurrencyType>,
+        tag: Option<String>,
+        price_min: Option<Decimal>,
+        price_max: Option<Decimal>,
+        market_cap_min: Option<Decimal>,
+        market_cap_max: Option<Decimal>,
+        volume_24h_min: Option<Decimal>,
+        percent_change_24h_min: Option<Decimal>,
+        aux: Vec<String>,
+    }
+
+    impl ListingQuery {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Offsets the start (1-based index) of the paginated list of items to return.
+        pub fn start(mut self, start: u32) -> Self {
+            self.start = Some(start);
+            self
+        }
+
+        /// Number of results to return (between 1 and 5000).
+        pub fn limit(mut self, limit: u32) -> Self {
+            self.limit = Some(limit);
+            self
+        }
+
+        /// Calculates the market quotes in this currency (`USD` by default).
+        pub fn convert(mut self, convert: &str) -> Self {
+            self.convert = Some(convert.to_string());
+            self
+        }
+
+        /// Field to sort the list of cryptocurrencies by (`market_cap` by default).
+        pub fn sort(mut self, sort: Sort) -> Self {
+            self.sort = Some(sort);
+            self
+        }
+
+        /// Direction in which to order cryptocurrencies against the specified sort.
+        pub fn sort_dir(mut self, sort_dir: SortDir) -> Self {
+            self.sort_dir = Some(sort_dir);
+            self
+        }
+
+        /// Type of cryptocurrency to include (`all` by default).
+        pub fn cryptocurrency_type(mut self, cryptocurrency_type: CryptocurrencyType) -> Self {
+            self.cryptocurrency_type = Some(cryptocurrency_type);
+            self
+        }
+
+        /// Only includes cryptocurrencies with this tag (e.g. `defi`).
+        pub fn tag(mut self, tag: &str) -> Self {
+            self.tag = Some(tag.to_string());
+            self
+        }
+
+        /// Only includes cryptocurrencies with a price between `min` and `max`, if set.
+        pub fn price(mut self, min: Option<Decimal>, max: Option<Decimal>) -> Self {
+            self.price_min = min;
+            self.price_max = max;
+            self
+        }
+
+        /// Only includes cryptocurrencies with a market cap between `min` and `max`, if set.
+        pub fn market_cap(mut self, min: Option<Decimal>, max: Option<Decimal>) -> Self {
+            self.market_cap_min = min;
+            self.market_cap_max = max;
+            self
+        }
+
+        /// Only includes cryptocurrencies with at least this 24 hour volume.
+        pub fn volume_24h_min(mut self, min: Decimal) -> Self {
+            self.volume_24h_min = Some(min);
+            self
+        }
+
+        /// Only includes cryptocurrencies with at least this 24 hour percent change.
+        pub fn percent_change_24h_min(mut self, min: Decimal) -> Self {
+            self.percent_change_24h_min = Some(min);
+            self
+        }
+
+        /// Selects the supplemental fields to return among `AUX_FIELDS`.
+        pub fn aux(mut self, aux: &[&str]) -> Self {
+            self.aux = aux.iter().map(|field| field.to_string()).collect();
+            self
+        }
+
+        /// Checks the parameters against the constraints documented by CoinMarketCap.
+        pub fn validate(&self) -> Result<(), CmcError> {
+            let invalid = |message: String| Err(CmcError::InvalidArgument(message));
+
+            if self.start == Some(0) {
+                return invalid("`start` is a 1-based index".to_string());
+            }
+            if let Some(limit) = self.limit {
+                if !(1..=5000).contains(&limit) {
+                    return invalid(format!("`limit` must be between 1 and 5000, not {}", limit));
+                }
+            }
+            if matches!(&self.convert, Some(convert) if convert.is_empty()) {
+                return invalid("`convert` is empty".to_string());
+            }
+            if matches!(&self.tag, Some(tag) if tag.is_empty()) {
+                return invalid("`tag` is empty".to_string());
+            }
+
+            let ranges = [
+                ("price", self.price_min, self.price_max),
+                ("market_cap", self.market_cap_min, self.market_cap_max),
+                ("volume_24h", self.volume_24h_min, None),
+            ];
+            for (name, min, max) in ranges {
+                if matches!(min, Some(min) if min.is_sign_negative())
+                    || matches!(max, Some(max) if max.is_sign_negative())
+                {
+                    return invalid(format!("`{}` bounds must be non-negative", name));
+                }
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        return invalid(format!("`{}_min` is greater than `{}_max`", name, name));
+                    }
+                }
+            }
+            if matches!(self.percent_change_24h_min, Some(min) if min < Decimal::new(-100, 0)) {
+                return invalid("`percent_change_24h_min` must be at least -100".to_string());
+            }
+            if let Some(field) = self
+                .aux
+                .iter()
+                .find(|field| !AUX_FIELDS.contains(&field.as_str()))
+            {
+                return invalid(format!("`{}` is not a supplemental field", field));
+            }
+
+            Ok(())
+        }
+
+        /// Returns the validated query parameters.
+        pub fn params(&self) -> Result<Vec<(&'static str, String)>, CmcError> {
+            self.validate()?;
+
+            let mut params = Vec::new();
+            let mut push = |name, value: Option<String>| {
+                if let Some(value) = value {
+                    params.push((name, value));
+                }
+            };
+
+            push("start", self.start.map(|start| start.to_string()));
+            push("limit", self.limit.map(|limit| limit.to_string()));
+            push("convert", self.convert.clone());
+            push("sort", self.sort.map(|sort| sort.as_str().to_string()));
+            push(
+                "sort_dir",
+                self.sort_dir.map(|sort_dir| sort_dir.as_str().to_string()),
+            );
+            push(
+                "cryptocurrency_type",
+                self.cryptocurrency_type
+                    .map(|cryptocurrency_type| cryptocurrency_type.as_str().to_string()),
+            );
+            push("tag", self.tag.clone());
+            push("price_min", self.price_min.map(|min| min.to_string()));
+            push("price_max", self.price_max.map(|max| max.to_string()));
+            push(
+                "market_cap_min",
+                self.market_cap_min.map(|min| min.to_string()),
+            );
+            push(
+                "market_cap_max",
+                self.market_cap_max.map(|max| max.to_string()),
+            );
+            push(
+                "volume_24h_min",
+                self.volume_24h_min.map(|min| min.to_string()),
+            );
+            push(
+                "percent_change_24h_min",
+                self.percent_change_24h_min.map(|min| min.to_string()),
+            );
+            if !self.aux.is_empty() {
+                push("aux", Some(self.aux.join(",")));
+            }
+
+            Ok(params)
+        }
+    }
+
     /// Makes a request to the endpoint `/v1/cryptocurrency/listings/latest` of the CoinMarketCap API.
     /// Returns a paginated list of all active cryptocurrencies with latest market data. The default
     /// `market_cap` sort returns cryptocurrency in order of CoinMarketCap's market cap rank.
     pub async fn request_crypto_listing(
+        client: &Client<'_>,
         start: u32,
         limit: u32,
         convert: &str,
     ) -> Result<Response, CmcError> {
-        let config = configuration::load_config()?;
-
-        // Pull new data from the server
-        let client = reqwest::Client::new();
-        let params = [
-            ("start", start.to_string()),
-            ("limit", limit.to_string()),
-            ("convert", convert.to_string()),
-        ];
-        let response: Response = client
-            .get(config.coin_market.base_url + "/v1/cryptocurrency/listings/latest")
-            .header("X-CMC_PRO_API_KEY", config.coin_market.api_key)
-            .query(&params)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let query = ListingQuery::new()
+            .start(start)
+            .limit(limit)
+            .convert(convert);
+        request_crypto_listing_with(client, &query).await
+    }
 
-        Ok(response)
+    /// Makes a request to the endpoint `/v1/cryptocurrency/listings/latest` of the CoinMarketCap API
+    /// with all the parameters in `query`, once validated.
+    pub async fn request_crypto_listing_with(
+        client: &Client<'_>,
+        query: &ListingQuery,
+    ) -> Result<Response, CmcError> {
+        send_request(client, ENDPOINT, &query.params()?).await
     }
 }
 
-pub struct App {
-    pool: PgPool,
+/// Module that consumes the endpoint `/v2/cryptocurrency/quotes/latest`, which returns the latest
+/// market quote of one or more cryptocurrencies, e.g. a watchlist outside of the top listings. It
+/// costs 1 call credit per 100 cryptocurrencies.
+pub mod quotes_latest {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    use super::{listing, send_request, Client, CmcError, Status};
+
+    pub const ENDPOINT: &str = "/v2/cryptocurrency/quotes/latest";
+
+    /// Maximum number of cryptocurrencies per request that cost a single call credit.
+    pub const MAX_IDS: usize = 100;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        /// Quotes keyed by CoinMarketCap's `id`, with the same fields as the listings.
+        pub data: HashMap<u32, listing::Data>,
+        pub status: Status,
+    }
+
+    /// Makes a request to the endpoint `/v2/cryptocurrency/quotes/latest` of the CoinMarketCap API.
+    /// Returns the latest USD quotes of the cryptocurrencies `ids`.
+    pub async fn request_crypto_quotes(
+        client: &Client<'_>,
+        ids: &[u32],
+    ) -> Result<Response, CmcError> {
+        if ids.is_empty() {
+            return Err(CmcError::InvalidArgument(
+                "at least one id is required".to_string(),
+            ));
+        }
+
+        let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+        let params = [("id", ids.join(",")), ("convert", "USD".to_string())];
+
+        send_request(client, ENDPOINT, &params).await
+    }
 }
 
-impl App {
-    pub fn new() -> Self {
-        let config = configuration::load_config().unwrap();
-        let pool = database::get_connection_pool(&config.database);
-        Self { pool }
+/// Module that consumes the endpoint `/v2/cryptocurrency/quotes/historical`. The latter returns an
+/// interval of historic market quotes for any cryptocurrency based on time and interval
+/// parameters. Each request costs 1 call credit per 100 historical data points returned (rounded
+/// up).
+pub mod quotes_historical {
+    use bigdecimal::BigDecimal;
+    use chrono::prelude::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    use super::{lenient_number, send_request, Client, CmcError, Status};
+
+    pub const ENDPOINT: &str = "/v2/cryptocurrency/quotes/historical";
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        /// Historical quotes keyed by CoinMarketCap's `id`.
+        pub data: HashMap<u32, Data>,
+        pub status: Status,
     }
 
-    pub async fn fetch_crypto_data(
-        &self,
-        start: u32,
-        limit: u32,
-        convert: &str,
-    ) -> Result<(), CmcError> {
-        let response_map = map::request_crypto_map(start, 2 * limit, "cmc_rank").await?;
-        let response_listing = listing::request_crypto_listing(start, limit, convert).await?;
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        /// The CoinMarketCap's `id`.
+        pub id: u32,
+        pub name: String,
+        pub symbol: String,
+        pub quotes: Vec<Quote>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Quote {
+        /// Timestamp of when this historical quote was recorded.
+        pub timestamp: DateTime<Utc>,
+        pub quote: QuoteUsd,
+    }
 
-        // TODO: Currently we repopulate all tables in each update, this would change to keep the
-        // previous data.
-        database::clear_all_tables(self.pool.clone()).await?;
-        database::update_crypto_map(response_map, self.pool.clone()).await?;
-        database::update_crypto_listing(response_listing, self.pool.clone()).await?;
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "UPPERCASE")]
+    pub struct QuoteUsd {
+        pub usd: Point,
+    }
 
-        Ok(())
+    #[derive(Debug, Deserialize)]
+    pub struct Point {
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub price: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub volume_24h: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub circulating_supply: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub total_supply: Option<BigDecimal>,
+        pub timestamp: DateTime<Utc>,
+    }
+
+    /// Makes a request to the endpoint `/v2/cryptocurrency/quotes/historical` of the CoinMarketCap
+    /// API. Returns at most `count` quotes of the cryptocurrency `id`, sampled every `interval`
+    /// (e.g. `5m`, `hourly`, `daily`) starting from `time_start`.
+    pub async fn request_quotes_historical(
+        client: &Client<'_>,
+        id: u32,
+        time_start: DateTime<Utc>,
+        count: u32,
+        interval: &str,
+        convert: &str,
+    ) -> Result<Response, CmcError> {
+        let params = [
+            ("id", id.to_string()),
+            ("time_start", time_start.to_rfc3339()),
+            ("count", count.to_string()),
+            ("interval", interval.to_string()),
+            ("convert", convert.to_string()),
+        ];
+
+        send_request(client, ENDPOINT, &params).await
     }
 }
 
+/// Module that consumes the endpoint `/v2/cryptocurrency/ohlcv/historical`. The latter returns
+/// historical OHLCV (Open, High, Low, Close, Volume) data along with market cap for any
+/// cryptocurrency using time interval parameters. Each request costs 1 call credit per 100 OHLCV
+/// data points returned (rounded up).
+pub mod ohlcv_historical {
+    use bigdecimal::BigDecimal;
+    use chrono::prelude::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    use super::{lenient_number, send_request, Client, CmcError, Status};
+
+    pub const ENDPOINT: &str = "/v2/cryptocurrency/ohlcv/historical";
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        /// Historical OHLCV data keyed by CoinMarketCap's `id`.
+        pub data: HashMap<u32, Data>,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        /// The CoinMarketCap's `id`.
+        pub id: u32,
+        pub name: String,
+        pub symbol: String,
+        pub quotes: Vec<Quote>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Quote {
+        /// Timestamp of the start of this time period.
+        pub time_open: DateTime<Utc>,
+        /// Timestamp of the end of this time period.
+        pub time_close: DateTime<Utc>,
+        pub time_high: Option<DateTime<Utc>>,
+        pub time_low: Option<DateTime<Utc>>,
+        pub quote: QuoteUsd,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "UPPERCASE")]
+    pub struct QuoteUsd {
+        pub usd: Ohlcv,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Ohlcv {
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub open: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub high: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub low: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub close: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub volume: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub market_cap: Option<BigDecimal>,
+        pub timestamp: DateTime<Utc>,
+    }
+
+    /// Makes a request to the endpoint `/v2/cryptocurrency/ohlcv/historical` of the CoinMarketCap
+    /// API. Returns at most `count` OHLCV periods of the cryptocurrency `id`, where `time_period`
+    /// is either `daily` or `hourly`, starting from `time_start`.
+    pub async fn request_ohlcv_historical(
+        client: &Client<'_>,
+        id: u32,
+        time_start: DateTime<Utc>,
+        count: u32,
+        time_period: &str,
+        convert: &str,
+    ) -> Result<Response, CmcError> {
+        let params = [
+            ("id", id.to_string()),
+            ("time_start", time_start.to_rfc3339()),
+            ("count", count.to_string()),
+            ("time_period", time_period.to_string()),
+            ("convert", convert.to_string()),
+        ];
+
+        send_request(client, ENDPOINT, &params).await
+    }
+}
+
+/// Module that consumes the endpoint `/v1/cryptocurrency/categories`. The latter returns
+/// information about all coin categories available on CoinMarketCap, including their aggregate
+/// market data.
+pub mod categories {
+    use bigdecimal::BigDecimal;
+    use chrono::prelude::*;
+    use serde::Deserialize;
+
+    use super::{lenient_number, send_request, Client, CmcError, Status};
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        pub data: Vec<Data>,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        /// The CoinMarketCap's category `id`.
+        pub id: String,
+        pub name: String,
+        pub title: Option<String>,
+        pub description: Option<String>,
+        /// Number of cryptocurrencies in the category.
+        pub num_tokens: u32,
+        /// Average price change of the cryptocurrencies in the category.
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub avg_price_change: Option<BigDecimal>,
+        /// Aggregate market cap of the cryptocurrencies in the category.
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub market_cap_change: Option<BigDecimal>,
+        /// Aggregate 24 hour volume of the cryptocurrencies in the category.
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub volume: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub volume_change: Option<BigDecimal>,
+        pub last_updated: DateTime<Utc>,
+    }
+
+    /// Makes a request to the endpoint `/v1/cryptocurrency/categories` of the CoinMarketCap API.
+    /// Returns a paginated list of all the coin categories.
+    pub async fn request_categories(
+        client: &Client<'_>,
+        start: u32,
+        limit: u32,
+    ) -> Result<Response, CmcError> {
+        let params = [("start", start.to_string()), ("limit", limit.to_string())];
+
+        send_request(client, "/v1/cryptocurrency/categories", &params).await
+    }
+}
+
+/// Module that consumes the endpoint `/v1/cryptocurrency/category`. The latter returns
+/// information about a single coin category, including the list of cryptocurrencies that belong
+/// to it with their latest market data.
+pub mod category {
+    use bigdecimal::BigDecimal;
+    use chrono::prelude::*;
+    use serde::Deserialize;
+
+    use super::{lenient_number, listing, send_request, Client, CmcError, Status};
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        pub data: Data,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        /// The CoinMarketCap's category `id`.
+        pub id: String,
+        pub name: String,
+        pub title: Option<String>,
+        pub description: Option<String>,
+        pub num_tokens: u32,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub avg_price_change: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub market_cap_change: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub volume: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub volume_change: Option<BigDecimal>,
+        /// Cryptocurrencies that belong to the category.
+        pub coins: Vec<listing::Data>,
+        pub last_updated: DateTime<Utc>,
+    }
+
+    /// Makes a request to the endpoint `/v1/cryptocurrency/category` of the CoinMarketCap API.
+    /// Returns the category `id` along with a paginated list of its cryptocurrencies.
+    pub async fn request_category(
+        client: &Client<'_>,
+        id: &str,
+        start: u32,
+        limit: u32,
+        convert: &str,
+    ) -> Result<Response, CmcError> {
+        let params = [
+            ("id", id.to_string()),
+            ("start", start.to_string()),
+            ("limit", limit.to_string()),
+            ("convert", convert.to_string()),
+        ];
+
+        send_request(client, "/v1/cryptocurrency/category", &params).await
+    }
+}
+
+/// Module that consumes the endpoints `/v1/cryptocurrency/trending/*`. These return paginated
+/// lists of cryptocurrencies with their latest market data, namely
+/// - `gainers-losers`: the biggest gainers (or losers) over a time period.
+/// - `latest`: the cryptocurrencies trending on CoinMarketCap over a time period.
+/// - `most-visited`: the most visited cryptocurrencies on CoinMarketCap over a time period.
+///
+/// **Remark:** These endpoints are not available on every subscription plan.
+pub mod trending {
+    use bigdecimal::BigDecimal;
+    use serde::Deserialize;
+
+    use super::{listing, send_request, Changes, Client, CmcError, Records, Status};
+
+    #[derive(Debug, Deserialize)]
+    #[serde(from = "Page")]
+    pub struct Response {
+        pub data: Vec<listing::Data>,
+        pub status: Status,
+        /// Number of malformed records left out of `data`.
+        pub skipped: u32,
+    }
+
+    /// Response as sent by the server, whose records are deserialized one at a time.
+    #[derive(Deserialize)]
+    struct Page {
+        data: Records<listing::Data>,
+        status: Status,
+    }
+
+    impl From<Page> for Response {
+        fn from(page: Page) -> Self {
+            Self {
+                data: page.data.valid,
+                status: page.status,
+                skipped: page.data.skipped,
+            }
+        }
+    }
+
+    /// Returns the percent change of `changes` over `time_period` (i.e. `1h`, `24h`, `7d`, `30d`,
+    /// `60d` or `90d`), if supported and available.
+    pub fn percent_change(changes: &Changes, time_period: &str) -> Option<BigDecimal> {
+        match time_period {
+            "1h" => changes.percent_change_1h.clone(),
+            "24h" => changes.percent_change_24h.clone(),
+            "7d" => changes.percent_change_7d.clone(),
+            "30d" => changes.percent_change_30d.clone(),
+            "60d" => changes.percent_change_60d.clone(),
+            "90d" => changes.percent_change_90d.clone(),
+            _ => None,
+        }
+    }
+
+    /// Makes a request to the endpoint `/v1/cryptocurrency/trending/gainers-losers` of the
+    /// CoinMarketCap API. Returns the biggest gainers over `time_period` (e.g. `1h`, `24h`, `30d`)
+    /// if `sort_dir` is `desc`, or the biggest losers if it is `asc`.
+    pub async fn request_gainers_losers(
+        client: &Client<'_>,
+        start: u32,
+        limit: u32,
+        time_period: &str,
+        sort_dir: &str,
+        convert: &str,
+    ) -> Result<Response, CmcError> {
+        let params = [
+            ("start", start.to_string()),
+            ("limit", limit.to_string()),
+            ("time_period", time_period.to_string()),
+            ("sort", format!("percent_change_{}", time_period)),
+            ("sort_dir", sort_dir.to_string()),
+            ("convert", convert.to_string()),
+        ];
+
+        send_request(
+            client,
+            "/v1/cryptocurrency/trending/gainers-losers",
+            &params,
+        )
+        .await
+    }
+
+    /// Makes a request to the endpoint `/v1/cryptocurrency/trending/latest` of the CoinMarketCap
+    /// API. Returns the cryptocurrencies trending over `time_period` (e.g. `24h`, `7d`, `30d`).
+    pub async fn request_trending_latest(
+        client: &Client<'_>,
+        start: u32,
+        limit: u32,
+        time_period: &str,
+        convert: &str,
+    ) -> Result<Response, CmcError> {
+        let params = [
+            ("start", start.to_string()),
+            ("limit", limit.to_string()),
+            ("time_period", time_period.to_string()),
+            ("convert", convert.to_string()),
+        ];
+
+        send_request(client, "/v1/cryptocurrency/trending/latest", &params).await
+    }
+
+    /// Makes a request to the endpoint `/v1/cryptocurrency/trending/most-visited` of the
+    /// CoinMarketCap API. Returns the most visited cryptocurrencies over `time_period` (e.g. `24h`,
+    /// `7d`, `30d`).
+    pub async fn request_most_visited(
+        client: &Client<'_>,
+        start: u32,
+        limit: u32,
+        time_period: &str,
+        convert: &str,
+    ) -> Result<Response, CmcError> {
+        let params = [
+            ("start", start.to_string()),
+            ("limit", limit.to_string()),
+            ("time_period", time_period.to_string()),
+            ("convert", convert.to_string()),
+        ];
+
+        send_request(client, "/v1/cryptocurrency/trending/most-visited", &params).await
+    }
+}
+
+/// Module that consumes the endpoint `/v1/key/info`, which reports the limits of the subscription
+/// plan of the API key and its current usage. This endpoint doesn't consume call credits.
+pub mod key_info {
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+
+    use super::{send_request, Client, CmcError, Status};
+
+    pub const ENDPOINT: &str = "/v1/key/info";
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        pub data: Data,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        pub plan: Plan,
+        pub usage: Usage,
+    }
+
+    impl Data {
+        /// Returns the number of call credits left until the monthly reset.
+        pub fn monthly_credits_left(&self) -> u32 {
+            self.usage.current_month.credits_left.unwrap_or_else(|| {
+                self.plan
+                    .credit_limit_monthly
+                    .saturating_sub(self.usage.current_month.credits_used)
+            })
+        }
+    }
+
+    /// Limits of the subscription plan. The daily credit limit is not enforced by every plan.
+    #[derive(Debug, Deserialize)]
+    pub struct Plan {
+        pub credit_limit_daily: Option<u32>,
+        /// Human-readable time until the daily reset, e.g. `In 19 hours, 56 minutes`.
+        pub credit_limit_daily_reset: Option<String>,
+        pub credit_limit_daily_reset_timestamp: Option<DateTime<Utc>>,
+        pub credit_limit_monthly: u32,
+        pub credit_limit_monthly_reset: Option<String>,
+        pub credit_limit_monthly_reset_timestamp: DateTime<Utc>,
+        pub rate_limit_minute: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Usage {
+        pub current_minute: RequestUsage,
+        pub current_day: CreditUsage,
+        pub current_month: CreditUsage,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct RequestUsage {
+        pub requests_made: u32,
+        pub requests_left: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct CreditUsage {
+        pub credits_used: u32,
+        /// Missing if the plan has no limit for the period (e.g. daily).
+        pub credits_left: Option<u32>,
+    }
+
+    /// Makes a request to the endpoint `/v1/key/info` of the CoinMarketCap API.
+    pub async fn request_key_info(client: &Client<'_>) -> Result<Response, CmcError> {
+        send_request(client, ENDPOINT, &[]).await
+    }
+}
+
+/// Module that consumes the endpoint `/v2/cryptocurrency/info`, which returns the static metadata
+/// of one or more cryptocurrencies, including the contracts of the tokens on every chain on which
+/// they are deployed. It costs 1 call credit per 100 cryptocurrencies.
+pub mod info {
+    use chrono::prelude::*;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    use super::{send_request, Client, CmcError, Platform, Status};
+
+    pub const ENDPOINT: &str = "/v2/cryptocurrency/info";
+
+    /// Maximum number of cryptocurrencies per request that cost a single call credit.
+    pub const MAX_IDS: usize = 100;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        /// Metadata keyed by CoinMarketCap's `id`.
+        pub data: HashMap<u32, Data>,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        /// The CoinMarketCap's `id`.
+        pub id: u32,
+        pub name: String,
+        pub symbol: String,
+        pub slug: String,
+        /// Either `coin` or `token`.
+        pub category: Option<String>,
+        pub description: Option<String>,
+        pub logo: Option<String>,
+        pub tags: Option<Vec<String>>,
+        pub date_added: Option<DateTime<Utc>>,
+        /// The platform on which the token was originally issued, if any.
+        pub platform: Option<Platform>,
+        /// Contracts of the token on every chain on which it is deployed (empty for coins).
+        #[serde(default)]
+        pub contract_address: Vec<ContractAddress>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ContractAddress {
+        pub contract_address: String,
+        pub platform: ContractPlatform,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ContractPlatform {
+        /// Name of the chain, e.g. `Binance Smart Chain (BEP20)`.
+        pub name: String,
+        /// Native coin of the chain.
+        pub coin: Coin,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Coin {
+        /// The CoinMarketCap's `id`, which this endpoint returns as a string.
+        #[serde(deserialize_with = "id_from_str")]
+        pub id: u32,
+        pub name: String,
+        pub symbol: String,
+        pub slug: String,
+    }
+
+    fn id_from_str<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::String(id) => id.parse().map_err(serde::de::Error::custom),
+            Value::Number(id) => id
+                .as_u64()
+                .and_then(|id| u32::try_from(id).ok())
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid id `{}`", id))),
+            other => Err(serde::de::Error::custom(format!("invalid id `{}`", other))),
+        }
+    }
+
+    /// Makes a request to the endpoint `/v2/cryptocurrency/info` of the CoinMarketCap API. Returns
+    /// the metadata of the cryptocurrencies `ids`.
+    pub async fn request_crypto_info(
+        client: &Client<'_>,
+        ids: &[u32],
+    ) -> Result<Response, CmcError> {
+        if ids.is_empty() {
+            return Err(CmcError::InvalidArgument(
+                "at least one id is required".to_string(),
+            ));
+        }
+
+        let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+        let params = [("id", ids.join(","))];
+
+        send_request(client, ENDPOINT, &params).await
+    }
+}
+
+/// Module that consumes the endpoint `/v1/global-metrics/quotes/latest`, which returns the latest
+/// aggregate market metrics, e.g. the total market cap and the dominance of Bitcoin. It costs 1
+/// call credit.
+pub mod global_metrics {
+    use bigdecimal::BigDecimal;
+    use chrono::prelude::*;
+    use serde::Deserialize;
+
+    use super::{lenient_number, send_request, Client, CmcError, Status};
+
+    pub const ENDPOINT: &str = "/v1/global-metrics/quotes/latest";
+
+    #[derive(Debug, Deserialize)]
+    pub struct Response {
+        pub data: Data,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Data {
+        pub active_cryptocurrencies: u32,
+        pub total_cryptocurrencies: Option<u32>,
+        pub active_market_pairs: u32,
+        pub active_exchanges: u32,
+        pub total_exchanges: Option<u32>,
+        /// Percentage of the total market cap held by Bitcoin.
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub btc_dominance: Option<BigDecimal>,
+        /// Percentage of the total market cap held by Ethereum.
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub eth_dominance: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub defi_volume_24h: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub defi_market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub stablecoin_volume_24h: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub stablecoin_market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub derivatives_volume_24h: Option<BigDecimal>,
+        pub quote: Usd,
+        pub last_updated: DateTime<Utc>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "UPPERCASE")]
+    pub struct Usd {
+        pub usd: Quote,
+    }
+
+    /// Aggregate market data of all the cryptocurrencies, in USD.
+    #[derive(Debug, Deserialize)]
+    pub struct Quote {
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub total_market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub total_volume_24h: Option<BigDecimal>,
+        /// Market cap of all the cryptocurrencies but Bitcoin.
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub altcoin_market_cap: Option<BigDecimal>,
+        #[serde(default, deserialize_with = "lenient_number")]
+        pub altcoin_volume_24h: Option<BigDecimal>,
+        pub last_updated: DateTime<Utc>,
+    }
+
+    /// Makes a request to the endpoint `/v1/global-metrics/quotes/latest` of the CoinMarketCap API.
+    pub async fn request_global_metrics(client: &Client<'_>) -> Result<Response, CmcError> {
+        send_request(client, ENDPOINT, &[("convert", "USD".to_string())]).await
+    }
+}
+
+/// Returns the message of `error` followed by those of its sources, e.g. `Issues querying the
+/// database: error returned from database: ...`.
+fn error_chain(error: &CmcError) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    message
+}
+
+/// Number of data points requested per page during a backfill. CoinMarketCap charges 1 call
+/// credit per 100 historical data points, so each page costs exactly one credit.
+const BACKFILL_PAGE_SIZE: u32 = 100;
+
+pub struct App {
+    config: Configuration,
+    pool: PgPool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        let config = configuration::load_config().unwrap();
+        let pool = database::get_connection_pool(&config.database);
+        Self { config, pool }
+    }
+
+    pub fn config(&self) -> &Configuration {
+        &self.config
+    }
+
+    /// Returns the client of the CoinMarketCap API, which reuses the configuration and the pool of
+    /// the app.
+    pub fn client(&self) -> Client<'_> {
+        Client::new(&self.config.coin_market, &self.pool)
+    }
+
+    /// Prepares the database before any other use. If enabled in the `database` section of the
+    /// configuration, the database is created and migrated first. Then, it refuses to go on if the
+    /// schema is older than the one expected by the binary.
+    pub async fn bootstrap(&self) -> Result<(), CmcError> {
+        if self.config.database.migrate {
+            self.migrate().await?;
+        }
+
+        let pending = self.pending_migrations().await?;
+        if !pending.is_empty() {
+            return Err(CmcError::OutdatedSchema(pending));
+        }
+
+        Ok(())
+    }
+
+    /// Creates the database if missing and runs the pending embedded migrations.
+    pub async fn migrate(&self) -> Result<(), CmcError> {
+        database::migrate(&self.config.database).await?;
+        Ok(())
+    }
+
+    /// Returns the versions of the embedded migrations not yet applied to the database.
+    pub async fn pending_migrations(&self) -> Result<Vec<i64>, CmcError> {
+        Ok(database::get_pending_migrations(self.pool.clone()).await?)
+    }
+
+    /// Returns a summary of the contents of the local cache.
+    pub async fn cache_status(&self) -> Result<database::CacheStatus, CmcError> {
+        Ok(database::get_cache_status(self.pool.clone()).await?)
+    }
+
+    /// Returns the `limit` most recent ingestion runs, newest first.
+    pub async fn ingest_runs(&self, limit: i64) -> Result<Vec<database::IngestRun>, CmcError> {
+        Ok(database::get_ingest_runs(limit, self.pool.clone()).await?)
+    }
+
+    /// Fetches the latest listings and appends them to `crypto_listing`. Every run is recorded in
+    /// `ingest_run`, along with the rows written, the call credits used and the error (if any).
+    pub async fn fetch_crypto_data(
+        &self,
+        start: u32,
+        limit: u32,
+        convert: &str,
+    ) -> Result<(), CmcError> {
+        let query = self.listing_query(start, limit, convert)?;
+        let params: Vec<String> = query
+            .params()?
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        let run =
+            database::start_ingest_run(listing::ENDPOINT, &params.join("&"), self.pool.clone())
+                .await?;
+
+        let mut progress = database::IngestProgress::default();
+        let result = self.ingest_crypto_data(&query, run, &mut progress).await;
+        let error = result.as_ref().err().map(error_chain);
+        if let Err(error) =
+            database::finish_ingest_run(run, &progress, error.as_deref(), self.pool.clone()).await
+        {
+            log::error!(
+                "Failed to record the end of the ingestion run {}: {}",
+                run,
+                error
+            );
+        }
+
+        result
+    }
+
+    async fn ingest_crypto_data(
+        &self,
+        query: &listing::ListingQuery,
+        run: i64,
+        progress: &mut database::IngestProgress,
+    ) -> Result<(), CmcError> {
+        let mut response = listing::request_crypto_listing_with(&self.client(), query).await?;
+        progress.credits_used += response.status.credit_count;
+        progress.rejected += response.skipped;
+        self.resolve_crypto_map(&mut response.data, progress)
+            .await?;
+        self.validate_listings(&mut response.data, Some(run), progress)
+            .await?;
+
+        // The listings are appended, so the history (e.g. backfilled quotes) is preserved between
+        // updates.
+        progress.rows += database::update_crypto_listing(response, self.pool.clone()).await?;
+
+        Ok(())
+    }
+
+    /// Makes sure that the cryptocurrencies listed in `data` are present in `crypto_map`, as
+    /// required by the foreign key of `crypto_listing`. The missing ones are fetched from the map
+    /// endpoint filtered by their symbols, and those that still can't be resolved are removed from
+    /// `data`.
+    async fn resolve_crypto_map(
+        &self,
+        data: &mut Vec<listing::Data>,
+        progress: &mut database::IngestProgress,
+    ) -> Result<(), CmcError> {
+        let ids: Vec<u32> = data.iter().map(|data| data.id).collect();
+        let missing = database::get_missing_crypto_map_ids(&ids, self.pool.clone()).await?;
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        // The map endpoint can't be filtered by `id`, but symbols are enough to narrow it down.
+        let symbols: BTreeSet<&str> = data
+            .iter()
+            .filter(|data| missing.contains(&data.id))
+            .map(|data| data.symbol.as_str())
+            .collect();
+        let query = map::MapQuery::new()
+            .listing_status(&[
+                map::ListingStatus::Active,
+                map::ListingStatus::Inactive,
+                map::ListingStatus::Untracked,
+            ])
+            .symbol(&symbols.into_iter().collect::<Vec<_>>())
+            .aux(&map::AUX_FIELDS);
+        let response = map::request_crypto_map_with(&self.client(), &query).await?;
+        progress.credits_used += response.status.credit_count;
+        progress.rows += database::update_crypto_map(response, self.pool.clone()).await?;
+
+        self.retain_mapped(data).await
+    }
+
+    /// Removes from `data` the cryptocurrencies that are not present in `crypto_map`.
+    async fn retain_mapped(&self, data: &mut Vec<listing::Data>) -> Result<(), CmcError> {
+        let ids: Vec<u32> = data.iter().map(|data| data.id).collect();
+        let unresolved = database::get_missing_crypto_map_ids(&ids, self.pool.clone()).await?;
+        if !unresolved.is_empty() {
+            log::warn!(
+                "Skipping the cryptocurrencies of ids {:?}: not found in the map",
+                unresolved
+            );
+            data.retain(|data| !unresolved.contains(&data.id));
+        }
+
+        Ok(())
+    }
+
+    /// Removes from `data` the listings failing the validation set in the `validation` section of
+    /// the configuration, and quarantines them on behalf of the ingestion run `run` if requested.
+    /// The offending records are logged, and counted in `progress`.
+    async fn validate_listings(
+        &self,
+        data: &mut Vec<listing::Data>,
+        run: Option<i64>,
+        progress: &mut database::IngestProgress,
+    ) -> Result<(), CmcError> {
+        let config = &self.config.validation;
+        if config.action == ValidationAction::Off || data.is_empty() {
+            return Ok(());
+        }
+
+        let previous_prices = database::get_previous_prices(data, self.pool.clone()).await?;
+        let mut validated = validation::validate(std::mem::take(data), &previous_prices, config);
+        *data = std::mem::take(&mut validated.valid);
+        if validated.invalid.is_empty() {
+            return Ok(());
+        }
+
+        for invalid in &validated.invalid {
+            log::warn!(
+                "Invalid listing of id {} at {}: {}",
+                invalid.data.id,
+                invalid.data.quote.usd.last_updated,
+                invalid.reasons().join(", ")
+            );
+        }
+        let violations: Vec<String> = validated
+            .violations()
+            .iter()
+            .map(|(rule, count)| format!("{} {}", count, rule))
+            .collect();
+        let count = validated.invalid.len() as u32;
+        match config.action {
+            ValidationAction::Quarantine => {
+                database::quarantine_crypto_listings(&validated.invalid, run, self.pool.clone())
+                    .await?;
+                progress.quarantined += count;
+                log::warn!(
+                    "Quarantined {} invalid listings ({})",
+                    count,
+                    violations.join(", ")
+                );
+            }
+            _ => {
+                progress.rejected += count;
+                log::warn!(
+                    "Rejected {} invalid listings ({})",
+                    count,
+                    violations.join(", ")
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the latest quotes of the watchlist `ids` (e.g. cryptocurrencies outside of the top
+    /// listings) and appends them to `crypto_listing` like the listings. Note that this costs one
+    /// call credit per 100 cryptocurrencies.
+    pub async fn fetch_watchlist_quotes(&self, ids: &[u32]) -> Result<(), CmcError> {
+        let mut progress = database::IngestProgress::default();
+        for ids in ids.chunks(quotes_latest::MAX_IDS) {
+            let response = quotes_latest::request_crypto_quotes(&self.client(), ids).await?;
+            progress.credits_used += response.status.credit_count;
+            let mut data: Vec<listing::Data> = response.data.into_values().collect();
+            self.resolve_crypto_map(&mut data, &mut progress).await?;
+            self.validate_listings(&mut data, None, &mut progress)
+                .await?;
+            progress.rows += database::insert_crypto_listings(&data, self.pool.clone()).await?;
+        }
+        log::info!(
+            "Fetched the quotes of {} watched cryptocurrencies ({} rows inserted, {} credits used)",
+            ids.len(),
+            progress.rows.inserted,
+            progress.credits_used
+        );
+
+        Ok(())
+    }
+
+    /// Imports a dump of responses of the map or listings endpoints (see the `import` module)
+    /// through the same upserts as the fetched responses, without consuming any call credit. The
+    /// endpoint of each snapshot is detected unless `dump` is given.
+    ///
+    /// Since the map is not requested either, listings of cryptocurrencies missing in `crypto_map`
+    /// are skipped. Hence the map snapshots are imported first, followed by the listings. Invalid
+    /// snapshots are skipped, along with the rest of their file if it can't be parsed.
+    pub async fn import(&self, path: &Path, dump: Option<Dump>) -> Result<ImportSummary, CmcError> {
+        let files = import::dump_files(path)?;
+        let mut summary = ImportSummary::default();
+
+        let mut listing_files = Vec::new();
+        for file in &files {
+            if self
+                .import_snapshots(file, dump, Dump::Map, &mut summary)
+                .await?
+            {
+                listing_files.push(file);
+            }
+        }
+        for file in listing_files {
+            self.import_snapshots(file, dump, Dump::Listing, &mut summary)
+                .await?;
+        }
+
+        Ok(summary)
+    }
+
+    /// Imports the snapshots of `kind` stored in `file`, returning whether it holds other kinds of
+    /// snapshots too. Invalid snapshots are only reported during the first pass, i.e. of the map.
+    async fn import_snapshots(
+        &self,
+        file: &Path,
+        dump: Option<Dump>,
+        kind: Dump,
+        summary: &mut ImportSummary,
+    ) -> Result<bool, CmcError> {
+        let report = kind == Dump::Map;
+        let mut others = false;
+
+        for snapshot in import::read_snapshots(file)? {
+            let snapshot = match snapshot {
+                Ok(snapshot) => snapshot,
+                Err(error) => {
+                    // The stream can't be resumed after a syntax error.
+                    if report {
+                        log::warn!("Skipping the rest of {}: {}", file.display(), error);
+                        summary.failed += 1;
+                    }
+                    break;
+                }
+            };
+            let empty = snapshot["data"].as_array().is_some_and(Vec::is_empty);
+
+            match dump.or_else(|| Dump::detect(&snapshot)) {
+                Some(found) if found == kind => match self.import_snapshot(snapshot, kind).await {
+                    Ok(()) if kind == Dump::Map => summary.maps += 1,
+                    Ok(()) => summary.listings += 1,
+                    Err(CmcError::Parse(error)) => {
+                        log::warn!("Skipping a snapshot of {}: {}", file.display(), error);
+                        summary.failed += 1;
+                    }
+                    Err(error) => return Err(error),
+                },
+                Some(_) => others = true,
+                None if report && !empty => {
+                    log::warn!(
+                        "Skipping a snapshot of {}: unknown endpoint",
+                        file.display()
+                    );
+                    summary.failed += 1;
+                }
+                None => {}
+            }
+        }
+
+        Ok(others)
+    }
+
+    async fn import_snapshot(&self, snapshot: Value, kind: Dump) -> Result<(), CmcError> {
+        match kind {
+            Dump::Map => {
+                let response: map::Response = serde_json::from_value(snapshot)?;
+                database::update_crypto_map(response, self.pool.clone()).await?;
+            }
+            Dump::Listing => {
+                let mut response: listing::Response = serde_json::from_value(snapshot)?;
+                self.retain_mapped(&mut response.data).await?;
+                let mut progress = database::IngestProgress::default();
+                self.validate_listings(&mut response.data, None, &mut progress)
+                    .await?;
+                database::update_crypto_listing(response, self.pool.clone()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the query of the listings fetched by the poller, including the filters set in the
+    /// `listing` section of the configuration.
+    fn listing_query(
+        &self,
+        start: u32,
+        limit: u32,
+        convert: &str,
+    ) -> Result<listing::ListingQuery, CmcError> {
+        let filters = &self.config.listing;
+
+        let mut query = listing::ListingQuery::new()
+            .start(start)
+            .limit(limit)
+            .convert(convert);
+        if let Some(sort) = &filters.sort {
+            query = query.sort(sort.parse()?);
+        }
+        if let Some(sort_dir) = &filters.sort_dir {
+            query = query.sort_dir(sort_dir.parse()?);
+        }
+        if let Some(cryptocurrency_type) = &filters.cryptocurrency_type {
+            query = query.cryptocurrency_type(cryptocurrency_type.parse()?);
+        }
+        if let Some(tag) = &filters.tag {
+            query = query.tag(tag);
+        }
+        query = query
+            .price(filters.price_min, filters.price_max)
+            .market_cap(filters.market_cap_min, filters.market_cap_max);
+        if let Some(volume_24h_min) = filters.volume_24h_min {
+            query = query.volume_24h_min(volume_24h_min);
+        }
+        if let Some(percent_change_24h_min) = filters.percent_change_24h_min {
+            query = query.percent_change_24h_min(percent_change_24h_min);
+        }
+
+        Ok(query)
+    }
+
+    /// Fetches the whole map of cryptocurrencies with the given listing statuses, page by page,
+    /// and updates the tables `crypto_map`, `platform` and `token_contract`. Cryptocurrencies that
+    /// are no longer active are kept with `is_active = false` and the timestamp of their delisting.
+    pub async fn fetch_crypto_map(
+        &self,
+        listing_status: &[map::ListingStatus],
+    ) -> Result<(), CmcError> {
+        const PAGE_SIZE: u32 = 5000;
+        let mut start = 1;
+
+        loop {
+            let query = map::MapQuery::new()
+                .start(start)
+                .limit(PAGE_SIZE)
+                .listing_status(listing_status)
+                .aux(&map::AUX_FIELDS);
+            let response = map::request_crypto_map_with(&self.client(), &query).await?;
+            let page_size = response.data.len() as u32;
+            database::update_crypto_map(response, self.pool.clone()).await?;
+
+            if page_size < PAGE_SIZE {
+                return Ok(());
+            }
+            start += PAGE_SIZE;
+        }
+    }
+
+    /// Fetches the contracts of the tokens `ids` (or of every token in `crypto_map` if empty) on
+    /// all the chains on which they are deployed, and updates the tables `platform` and
+    /// `token_contract`. Ids not present in `crypto_map` are skipped. Note that this costs one call
+    /// credit per 100 tokens.
+    pub async fn fetch_token_contracts(&self, ids: &[u32]) -> Result<(), CmcError> {
+        let mut ids = if ids.is_empty() {
+            database::get_token_ids(self.pool.clone()).await?
+        } else {
+            ids.to_vec()
+        };
+        let missing = database::get_missing_crypto_map_ids(&ids, self.pool.clone()).await?;
+        if !missing.is_empty() {
+            log::warn!(
+                "Skipping the contracts of ids {:?}: not found in the map",
+                missing
+            );
+            ids.retain(|id| !missing.contains(id));
+        }
+
+        for ids in ids.chunks(info::MAX_IDS) {
+            let response = info::request_crypto_info(&self.client(), ids).await?;
+            database::update_token_contracts(&response, self.pool.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches all the coin categories along with the cryptocurrencies that belong to each of them
+    /// and updates the tables `category` and `crypto_category`. Note that this costs at least one
+    /// call credit per category.
+    pub async fn fetch_categories(&self) -> Result<(), CmcError> {
+        let response = categories::request_categories(&self.client(), 1, 5000).await?;
+        database::update_categories(&response, self.pool.clone()).await?;
+
+        for data in &response.data {
+            let response = category::request_category(
+                &self.client(),
+                &data.id,
+                1,
+                data.num_tokens.max(1),
+                "USD",
+            )
+            .await?;
+            database::update_category_members(&response, self.pool.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the trending feeds (i.e. gainers, losers, latest and most visited) and stores a
+    /// snapshot of each of them in `crypto_trending`, as set in the `trending` section of the
+    /// configuration. Feeds not supported by the subscription plan are skipped, while the
+    /// cryptocurrencies missing from `crypto_map` are resolved like those of the listings.
+    pub async fn fetch_trending(&self) -> Result<(), CmcError> {
+        let trending = &self.config.trending;
+        let (limit, period) = (trending.limit, trending.time_period.as_str());
+        let fetched_at = Utc::now();
+        let feeds = [
+            (
+                "gainers",
+                trending::request_gainers_losers(&self.client(), 1, limit, period, "desc", "USD")
+                    .await,
+            ),
+            (
+                "losers",
+                trending::request_gainers_losers(&self.client(), 1, limit, period, "asc", "USD")
+                    .await,
+            ),
+            (
+                "latest",
+                trending::request_trending_latest(&self.client(), 1, limit, period, "USD").await,
+            ),
+            (
+                "most_visited",
+                trending::request_most_visited(&self.client(), 1, limit, period, "USD").await,
+            ),
+        ];
+
+        let mut progress = database::IngestProgress::default();
+        for (feed, response) in feeds {
+            match response {
+                Ok(mut response) => {
+                    self.resolve_crypto_map(&mut response.data, &mut progress)
+                        .await?;
+                    database::update_crypto_trending(
+                        &response,
+                        feed,
+                        period,
+                        fetched_at,
+                        self.pool.clone(),
+                    )
+                    .await?
+                }
+                Err(CmcError::Api {
+                    error_code,
+                    error_message,
+                }) => log::warn!(
+                    "Skipping the trending feed `{}` ({}): {}",
+                    feed,
+                    error_code,
+                    error_message
+                ),
+                Err(error) => return Err(error),
+            }
+        }
+        if progress.credits_used > 0 {
+            log::info!(
+                "Resolved the trending cryptocurrencies missing from the map ({} credits used)",
+                progress.credits_used
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the latest global market metrics (e.g. total market cap, dominance of Bitcoin) and
+    /// stores a snapshot of them in `global_metrics`.
+    pub async fn fetch_global_metrics(&self) -> Result<(), CmcError> {
+        let response = global_metrics::request_global_metrics(&self.client()).await?;
+        database::update_global_metrics(&response.data, self.pool.clone()).await?;
+
+        Ok(())
+    }
+
+    /// Maintains the history of the listings (see `retention`): creates the missing monthly
+    /// partitions of `crypto_listing`, rolls up the latest snapshots into hourly and daily
+    /// aggregates and drops the partitions older than the retention set in the configuration.
+    pub async fn apply_retention(&self) -> Result<RetentionSummary, CmcError> {
+        let summary =
+            retention::apply(&self.config.retention, Utc::now(), self.pool.clone()).await?;
+        log::info!(
+            "Retention applied ({} partitions created, {} aggregates written, {} partitions \
+             dropped)",
+            summary.partitions_created,
+            summary.aggregates,
+            summary.partitions_dropped.len()
+        );
+
+        Ok(summary)
+    }
+
+    /// Fetches the plan limits and current usage of the API key, logs them and stores them in
+    /// `key_usage`. A warning is logged when less than 10% of the monthly credits are left.
+    pub async fn fetch_key_info(&self) -> Result<key_info::Data, CmcError> {
+        let response = key_info::request_key_info(&self.client()).await?;
+        database::update_key_usage(&response, Utc::now(), self.pool.clone()).await?;
+
+        let data = response.data;
+        let (plan, usage) = (&data.plan, &data.usage);
+        let monthly_credits_left = data.monthly_credits_left();
+        log::info!(
+            "API key usage: {}/{} requests this minute, {} credits today ({} left), {}/{} credits \
+             this month ({} left, reset at {})",
+            usage.current_minute.requests_made,
+            plan.rate_limit_minute,
+            usage.current_day.credits_used,
+            usage
+                .current_day
+                .credits_left
+                .map_or_else(|| "unlimited".to_string(), |left| left.to_string()),
+            usage.current_month.credits_used,
+            plan.credit_limit_monthly,
+            monthly_credits_left,
+            plan.credit_limit_monthly_reset_timestamp
+        );
+        if monthly_credits_left < plan.credit_limit_monthly / 10 {
+            log::warn!(
+                "Only {} call credits left until {}",
+                monthly_credits_left,
+                plan.credit_limit_monthly_reset_timestamp
+            );
+        }
+
+        Ok(data)
+    }
+
+    /// Backfills the history of the cryptocurrencies `ids` between `time_start` and `time_end`
+    /// using the historical quotes and OHLCV endpoints, writing into `crypto_listing` and
+    /// `crypto_ohlcv` respectively. The progress of each id is stored, so an interrupted backfill
+    /// resumes from the last stored timestamp. The backfill stops as soon as the next request would
+    /// exceed the configured credit budget. Returns the number of credits used.
+    pub async fn backfill(
+        &self,
+        ids: &[u32],
+        time_start: DateTime<Utc>,
+        time_end: DateTime<Utc>,
+    ) -> Result<u32, CmcError> {
+        // Refuse to start if the backfill would use more credits than the ones left this month.
+        let required = self
+            .backfill_estimate(ids, time_start, time_end)
+            .await?
+            .min(self.config.backfill.credit_budget);
+        let remaining = self.fetch_key_info().await?.monthly_credits_left();
+        if required > remaining {
+            return Err(CmcError::InsufficientCredits {
+                required,
+                remaining,
+            });
+        }
+
+        let mut credits = 0;
+        for &id in ids {
+            // The foreign key constraints require the cryptocurrency to be already mapped.
+            if !database::crypto_map_contains(id, self.pool.clone()).await? {
+                log::warn!(
+                    "Skipping the backfill of id {}: not found in `crypto_map`",
+                    id
+                );
+                continue;
+            }
+
+            let completed = self
+                .backfill_quotes(id, time_start, time_end, &mut credits)
+                .await?
+                && self
+                    .backfill_ohlcv(id, time_start, time_end, &mut credits)
+                    .await?;
+
+            if !completed {
+                log::warn!(
+                    "Backfill stopped at id {}: credit budget of {} exhausted",
+                    id,
+                    self.config.backfill.credit_budget
+                );
+                break;
+            }
+        }
+
+        Ok(credits)
+    }
+
+    /// Returns an upper bound of the credits needed to backfill the cryptocurrencies `ids` between
+    /// `time_start` and `time_end`, taking into account the progress of previous backfills.
+    async fn backfill_estimate(
+        &self,
+        ids: &[u32],
+        time_start: DateTime<Utc>,
+        time_end: DateTime<Utc>,
+    ) -> Result<u32, CmcError> {
+        let backfill = &self.config.backfill;
+        let endpoints = [
+            (quotes_historical::ENDPOINT, backfill.interval.as_str()),
+            (ohlcv_historical::ENDPOINT, backfill.time_period.as_str()),
+        ];
+        let mut credits = 0;
+
+        for &id in ids {
+            for (endpoint, interval) in endpoints {
+                let step = interval_duration(interval)?;
+                let cursor = self
+                    .backfill_cursor(id, endpoint, time_start, time_end)
+                    .await?;
+                let points = ((time_end - cursor).num_seconds().max(0) / step.num_seconds()) + 1;
+                credits += (points as u32).div_ceil(BACKFILL_PAGE_SIZE);
+            }
+        }
+
+        Ok(credits)
+    }
+
+    /// Returns the timestamp from which the backfill of `endpoint` for the cryptocurrency `id`
+    /// must (re)start.
+    async fn backfill_cursor(
+        &self,
+        id: u32,
+        endpoint: &str,
+        time_start: DateTime<Utc>,
+        time_end: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, CmcError> {
+        let progress = database::get_backfill_progress(id, endpoint, self.pool.clone()).await?;
+
+        Ok(match progress {
+            Some(last) if last >= time_start && last < time_end => last + Duration::seconds(1),
+            _ => time_start,
+        })
+    }
+
+    /// Backfills `crypto_listing` with historical quotes of `id`. Returns `false` if the credit
+    /// budget was exhausted before completion.
+    async fn backfill_quotes(
+        &self,
+        id: u32,
+        time_start: DateTime<Utc>,
+        time_end: DateTime<Utc>,
+        credits: &mut u32,
+    ) -> Result<bool, CmcError> {
+        let backfill = &self.config.backfill;
+        let endpoint = quotes_historical::ENDPOINT;
+        let mut cursor = self
+            .backfill_cursor(id, endpoint, time_start, time_end)
+            .await?;
+
+        while cursor < time_end {
+            if *credits + 1 > backfill.credit_budget {
+                return Ok(false);
+            }
+
+            let mut response = quotes_historical::request_quotes_historical(
+                &self.client(),
+                id,
+                cursor,
+                BACKFILL_PAGE_SIZE,
+                &backfill.interval,
+                "USD",
+            )
+            .await?;
+            *credits += response.status.credit_count;
+
+            let quotes = match response.data.get_mut(&id) {
+                Some(data) => &mut data.quotes,
+                None => break,
+            };
+            let page_size = quotes.len();
+            quotes.retain(|quote| quote.timestamp >= cursor && quote.timestamp <= time_end);
+
+            let last = match quotes.last() {
+                Some(quote) => quote.timestamp,
+                None => break,
+            };
+            database::update_quotes_historical(&response, self.pool.clone()).await?;
+            database::set_backfill_progress(id, endpoint, last, self.pool.clone()).await?;
+            log::info!("Backfilled quotes of id {} up to {}", id, last);
+
+            if page_size < BACKFILL_PAGE_SIZE as usize {
+                break;
+            }
+            cursor = last + Duration::seconds(1);
+        }
+
+        Ok(true)
+    }
+
+    /// Backfills `crypto_ohlcv` with historical OHLCV data of `id`. Returns `false` if the credit
+    /// budget was exhausted before completion.
+    async fn backfill_ohlcv(
+        &self,
+        id: u32,
+        time_start: DateTime<Utc>,
+        time_end: DateTime<Utc>,
+        credits: &mut u32,
+    ) -> Result<bool, CmcError> {
+        let backfill = &self.config.backfill;
+        let endpoint = ohlcv_historical::ENDPOINT;
+        let mut cursor = self
+            .backfill_cursor(id, endpoint, time_start, time_end)
+            .await?;
+
+        while cursor < time_end {
+            if *credits + 1 > backfill.credit_budget {
+                return Ok(false);
+            }
+
+            let mut response = ohlcv_historical::request_ohlcv_historical(
+                &self.client(),
+                id,
+                cursor,
+                BACKFILL_PAGE_SIZE,
+                &backfill.time_period,
+                "USD",
+            )
+            .await?;
+            *credits += response.status.credit_count;
+
+            let quotes = match response.data.get_mut(&id) {
+                Some(data) => &mut data.quotes,
+                None => break,
+            };
+            let page_size = quotes.len();
+            quotes.retain(|quote| quote.time_open >= cursor && quote.time_open <= time_end);
+
+            let last = match quotes.last() {
+                Some(quote) => quote.time_open,
+                None => break,
+            };
+            database::update_ohlcv_historical(&response, &backfill.time_period, self.pool.clone())
+                .await?;
+            database::set_backfill_progress(id, endpoint, last, self.pool.clone()).await?;
+            log::info!("Backfilled OHLCV of id {} up to {}", id, last);
+
+            if page_size < BACKFILL_PAGE_SIZE as usize {
+                break;
+            }
+            cursor = last + Duration::seconds(1);
+        }
+
+        Ok(true)
+    }
+}
+
+/// Returns the (approximate) duration between two data points of a historical `interval`, e.g.
+/// `5m`, `1h`, `7d`, `hourly` or `daily`.
+pub fn interval_duration(interval: &str) -> Result<Duration, CmcError> {
+    let unsupported = || CmcError::InvalidArgument(format!("unsupported interval `{}`", interval));
+    let duration = match interval {
+        "hourly" => Duration::hours(1),
+        "daily" => Duration::days(1),
+        "weekly" => Duration::weeks(1),
+        "monthly" => Duration::days(30),
+        "yearly" => Duration::days(365),
+        _ => {
+            // The units are ASCII, so the value is all but the last byte.
+            let (value, unit_seconds) = match interval.chars().last() {
+                Some('m') => (&interval[..interval.len() - 1], 60),
+                Some('h') => (&interval[..interval.len() - 1], 60 * 60),
+                Some('d') => (&interval[..interval.len() - 1], 24 * 60 * 60),
+                _ => return Err(unsupported()),
+            };
+            let value: i64 = value
+                .parse()
+                .ok()
+                .filter(|&value| value > 0)
+                .ok_or_else(unsupported)?;
+            // `Duration` is bounded by `i64::MAX` milliseconds.
+            let seconds = value
+                .checked_mul(unit_seconds)
+                .filter(|&seconds| seconds <= i64::MAX / 1000)
+                .ok_or_else(|| {
+                    CmcError::InvalidArgument(format!("interval `{}` is too long", interval))
+                })?;
+            Duration::seconds(seconds)
+        }
+    };
+
+    Ok(duration)
+}
+
 impl Default for App {
     fn default() -> Self {
         Self::new()
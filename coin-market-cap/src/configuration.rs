@@ -1,14 +1,21 @@
 //! Manage the application configuration hierarchically using the content of `config` directory.
 
+use rust_decimal::Decimal;
 use serde::Deserialize;
-use sqlx::postgres::PgConnectOptions;
-use sqlx::ConnectOptions;
 use std::convert::{TryFrom, TryInto};
+use std::path::PathBuf;
+
+pub use cmc_common::config::DbConfig;
+
+use crate::cassette::CassetteMode;
+use crate::validation::ValidationAction;
 
 /// Possible runtime environment of the application.
 pub enum Env {
     Development,
     Production,
+    /// Development settings replaying the recorded cassettes, so the client runs offline.
+    Test,
 }
 
 impl Env {
@@ -16,6 +23,7 @@ impl Env {
         match self {
             Env::Development => "dev",
             Env::Production => "prod",
+            Env::Test => "test",
         }
     }
 }
@@ -27,8 +35,9 @@ impl TryFrom<String> for Env {
         match s.to_lowercase().as_str() {
             "dev" => Ok(Self::Development),
             "prod" => Ok(Self::Production),
+            "test" => Ok(Self::Test),
             other => Err(format!(
-                "{} is not a supported environment. Use either `dev` or `prod`.",
+                "{} is not a supported environment. Use either `dev`, `prod` or `test`.",
                 other
             )),
         }
@@ -39,39 +48,264 @@ impl TryFrom<String> for Env {
 pub struct Configuration {
     pub database: DbConfig,
     pub coin_market: CoinMarketConfig,
+    pub backfill: BackfillConfig,
+    pub trending: TrendingConfig,
+    #[serde(default)]
+    pub listing: ListingConfig,
+    #[serde(default)]
+    pub poller: PollerConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub watchlist: WatchlistConfig,
+    #[serde(default)]
+    pub leader_election: LeaderConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct CoinMarketConfig {
     pub base_url: String,
     pub api_key: String,
+    /// Whether to record the unknown and missing fields of every response in `schema_drift`.
+    #[serde(default)]
+    pub detect_schema_drift: bool,
+    #[serde(default)]
+    pub cassette: CassetteConfig,
+}
+
+/// Recording or replaying of the requests to the API (see `cassette`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct CassetteConfig {
+    #[serde(default)]
+    pub mode: CassetteMode,
+    /// Directory of the cassettes, relative to the current directory.
+    #[serde(default = "CassetteConfig::default_dir")]
+    pub dir: PathBuf,
+}
+
+impl CassetteConfig {
+    fn default_dir() -> PathBuf {
+        PathBuf::from("tests/cassettes")
+    }
+}
+
+impl Default for CassetteConfig {
+    fn default() -> Self {
+        Self {
+            mode: CassetteMode::default(),
+            dir: Self::default_dir(),
+        }
+    }
+}
+
+/// Settings of the historical backfill.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackfillConfig {
+    /// Sampling interval of the historical quotes (e.g. `5m`, `hourly`, `daily`).
+    pub interval: String,
+    /// Time period of the historical OHLCV data, either `hourly` or `daily`.
+    pub time_period: String,
+    /// Maximum number of call credits that a single backfill may consume.
+    pub credit_budget: u32,
+}
+
+/// Filters of the listings fetched by the poller (see `coin_market::listing::ListingQuery`).
+/// Unset filters are left to the server defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListingConfig {
+    pub sort: Option<String>,
+    pub sort_dir: Option<String>,
+    /// Either `all`, `coins` or `tokens`.
+    pub cryptocurrency_type: Option<String>,
+    pub tag: Option<String>,
+    pub price_min: Option<Decimal>,
+    pub price_max: Option<Decimal>,
+    pub market_cap_min: Option<Decimal>,
+    pub market_cap_max: Option<Decimal>,
+    pub volume_24h_min: Option<Decimal>,
+    pub percent_change_24h_min: Option<Decimal>,
+}
+
+/// Reaction of the daemon to the failures of its cycles (see `poller`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PollerConfig {
+    /// Number of consecutive failed cycles of a job before the daemon exits (0 to never exit).
+    pub max_consecutive_failures: u32,
+    /// Number of retries of a cycle after a transient, rate limit or database error.
+    pub max_retries: u32,
+    /// Seconds before the first retry, doubled on each subsequent one.
+    pub backoff_initial: u64,
+    /// Maximum number of seconds between two retries.
+    pub backoff_max: u64,
+}
+
+impl Default for PollerConfig {
+    fn default() -> Self {
+        Self {
+            max_consecutive_failures: 5,
+            max_retries: 3,
+            backoff_initial: 5,
+            backoff_max: 300,
+        }
+    }
+}
+
+/// Jobs run by the daemon (see `scheduler`). Without this section, only the listings (every 5
+/// minutes) and the usage of the API key (every hour) are fetched, and the retention of the
+/// history of the listings is applied (every hour).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Whole map of cryptocurrencies.
+    pub map: JobConfig,
+    /// Latest listings.
+    pub listing: JobConfig,
+    /// Trending feeds, if supported by the subscription plan.
+    pub trending: JobConfig,
+    /// Latest quotes of the `watchlist`.
+    pub quotes: JobConfig,
+    /// Contracts of the tokens of the map.
+    pub info: JobConfig,
+    /// Global market metrics.
+    pub global: JobConfig,
+    /// Plan limits and usage of the API key.
+    pub key_info: JobConfig,
+    /// Partitions, rollups and retention of the history of the listings.
+    pub retention: JobConfig,
 }
 
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        let every = |enabled, interval| JobConfig {
+            enabled,
+            interval: Some(interval),
+            cron: None,
+            jitter: 0,
+        };
+
+        Self {
+            map: every(false, 24 * 60 * 60),
+            listing: every(true, 5 * 60),
+            trending: every(false, 5 * 60),
+            quotes: every(false, 60),
+            info: every(false, 7 * 24 * 60 * 60),
+            global: every(false, 15 * 60),
+            key_info: every(true, 60 * 60),
+            retention: every(true, 60 * 60),
+        }
+    }
+}
+
+/// Timing of a job, run either every `interval` seconds or on a `cron` expression.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds between the end of a run and the start of the next one.
+    pub interval: Option<u64>,
+    /// Cron expression in UTC with seconds, e.g. `0 0 3 * * *` (daily at 3:00), which takes
+    /// precedence over `interval`.
+    pub cron: Option<String>,
+    /// Maximum number of seconds by which each run is randomly delayed, to spread the requests.
+    #[serde(default)]
+    pub jitter: u64,
+}
+
+/// Cryptocurrencies whose quotes are fetched by the `quotes` job, e.g. outside of the top listings.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WatchlistConfig {
+    /// CoinMarketCap ids.
+    #[serde(default)]
+    pub ids: Vec<u32>,
+}
+
+/// Election of a leader per job among the replicas of the daemon (see `leader`).
 #[derive(Debug, Clone, Deserialize)]
-pub struct DbConfig {
-    pub username: String,
-    pub password: String,
-    pub port: u16,
-    pub host: String,
-    pub database_name: String,
-}
-
-impl DbConfig {
-    pub fn without_db(&self) -> PgConnectOptions {
-        PgConnectOptions::new()
-            .host(&self.host)
-            .username(&self.username)
-            .password(&self.password)
-            .port(self.port)
+#[serde(default)]
+pub struct LeaderConfig {
+    /// Whether each job only runs on the replica leading it, otherwise every replica runs it.
+    pub enabled: bool,
+    /// First key of the advisory locks, shared by the replicas of a deployment (the second one
+    /// identifies the job).
+    pub lock_namespace: i32,
+}
+
+impl Default for LeaderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // `CMC` in ASCII.
+            lock_namespace: 0x434D43,
+        }
     }
+}
 
-    pub fn with_db(&self) -> PgConnectOptions {
-        let mut options = self.without_db().database(&self.database_name);
-        options.log_statements(log::LevelFilter::Trace);
-        options
+/// Rules checked on the listings before they are stored (see `validation`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    /// What to do with the listings breaking any rule.
+    pub action: ValidationAction,
+    /// Whether a price must be positive.
+    pub zero_price: bool,
+    /// Whether the supplies must not be negative.
+    pub negative_supply: bool,
+    /// Whether the circulating supply must not exceed the maximum supply.
+    pub supply_above_max: bool,
+    /// Maximum change of price, in percent, since the previous snapshot (none to disable). It
+    /// applies to both rises and falls, measured from the lower of both prices.
+    pub max_price_change: Option<f64>,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            action: ValidationAction::default(),
+            zero_price: true,
+            negative_supply: true,
+            supply_above_max: true,
+            max_price_change: Some(10_000.0),
+        }
     }
 }
 
+/// History of the listings kept by the `retention` job (see `retention`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Days of raw snapshots kept in `crypto_listing`, whose older monthly partitions are dropped
+    /// once rolled up (none to keep them forever).
+    pub raw_max_age: Option<u32>,
+    /// Hours of snapshots rolled up again on every run, so that late snapshots are aggregated too.
+    pub rollup_lookback: u32,
+    /// Number of monthly partitions created ahead of the current month.
+    pub premake_months: u32,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            raw_max_age: None,
+            rollup_lookback: 48,
+            premake_months: 1,
+        }
+    }
+}
+
+/// Settings of the trending feeds (i.e. gainers, losers, latest and most visited).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrendingConfig {
+    /// Time period of the feeds (e.g. `1h`, `24h`, `7d`, `30d`).
+    pub time_period: String,
+    /// Number of cryptocurrencies fetched per feed.
+    pub limit: u32,
+}
+
 pub fn load_config() -> Result<Configuration, config::ConfigError> {
     let mut settings = config::Config::default();
     let base_path = std::env::current_dir().expect("Failed to determine the current directory");
@@ -1,66 +1,211 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use bigdecimal::BigDecimal;
+use chrono::prelude::*;
+use sqlx::{postgres::PgPoolOptions, PgConnection, PgPool};
 
-use crate::coin_market::{listing, map};
+pub use cmc_common::database::{get_pending_migrations, migrate, MIGRATOR};
+
+use crate::coin_market::{
+    categories, category, global_metrics, info, key_info, listing, map, ohlcv_historical,
+    quotes_historical, trending, Platform,
+};
 use crate::configuration::DbConfig;
+use crate::retention::Resolution;
+use crate::schema_drift::Drift;
+use crate::validation::Invalid;
+use std::collections::HashMap;
+
+/// Number of rows written by an update.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RowCount {
+    pub inserted: u64,
+    pub updated: u64,
+}
+
+impl RowCount {
+    fn count(&mut self, inserted: bool) {
+        if inserted {
+            self.inserted += 1;
+        } else {
+            self.updated += 1;
+        }
+    }
+}
 
-/// Update the databases `crypto_map` and `platforms` with data from `map::Response`.
+impl std::ops::AddAssign for RowCount {
+    fn add_assign(&mut self, other: Self) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+    }
+}
+
+/// Update the databases `crypto_map`, `platform` and `token_contract` with data from
+/// `map::Response`. Existing rows are updated in place, and `delisted_at` is set once a
+/// cryptocurrency is no longer active (or cleared if it becomes active again). Fields missing in the
+/// response are left untouched, as are the name, symbol, slug and rank of the cryptocurrencies
+/// whose current alias is not older than the response (e.g. an older snapshot imported). Changes
+/// of name, symbol or slug are recorded in `crypto_alias`. Returns the number of rows of
+/// `crypto_map` inserted and updated.
 // TODO: Keep an eye on the development around `sqlx::FromRow`.
-pub async fn update_crypto_map(response: map::Response, pool: PgPool) -> Result<(), sqlx::Error> {
+pub async fn update_crypto_map(
+    response: map::Response,
+    pool: PgPool,
+) -> Result<RowCount, sqlx::Error> {
+    let mut rows = RowCount::default();
     for data in &response.data {
         let mut platform_id = None;
-
         if let Some(platform) = &data.platform {
-            platform_id = Some(data.id as i32);
-            sqlx::query!(
-                "INSERT INTO crypto_platform VALUES ($1, $2, $3);",
-                data.id as i32,     // crypto_map's derived blockchain id
-                platform.id as i32, // crypto_map's base blockchain id
-                platform.token_address,
-            )
-            .execute(&pool)
-            .await?;
+            platform_id = Some(update_token_platform(platform, pool.clone()).await?);
         }
 
-        sqlx::query!(
-            "INSERT INTO crypto_map VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9);",
+        let alias_from = sqlx::query_scalar!(
+            "SELECT valid_from FROM crypto_alias WHERE id = $1 AND valid_to IS NULL;",
+            data.id as i32
+        )
+        .fetch_optional(&pool)
+        .await?;
+        let newer = alias_from.is_none_or(|valid_from| valid_from < response.status.timestamp);
+
+        let inserted = sqlx::query!(
+            r#"INSERT INTO crypto_map (id, name, symbol, slug, rank, is_active,
+                                       first_historical_data, last_historical_data, platform,
+                                       status, delisted_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+                       CASE WHEN $6 = FALSE THEN COALESCE($8, now()) END)
+               ON CONFLICT (id) DO UPDATE
+               SET name = CASE WHEN $11 THEN EXCLUDED.name ELSE crypto_map.name END,
+                   symbol = CASE WHEN $11 THEN EXCLUDED.symbol ELSE crypto_map.symbol END,
+                   slug = CASE WHEN $11 THEN EXCLUDED.slug ELSE crypto_map.slug END,
+                   rank = CASE WHEN $11 THEN COALESCE(EXCLUDED.rank, crypto_map.rank)
+                               ELSE crypto_map.rank END,
+                   is_active = COALESCE(EXCLUDED.is_active, crypto_map.is_active),
+                   first_historical_data = COALESCE(EXCLUDED.first_historical_data,
+                                                    crypto_map.first_historical_data),
+                   last_historical_data = COALESCE(EXCLUDED.last_historical_data,
+                                                   crypto_map.last_historical_data),
+                   platform = COALESCE(EXCLUDED.platform, crypto_map.platform),
+                   status = COALESCE(EXCLUDED.status, crypto_map.status),
+                   delisted_at = CASE
+                       WHEN EXCLUDED.is_active IS NULL THEN crypto_map.delisted_at
+                       WHEN EXCLUDED.is_active THEN NULL
+                       ELSE COALESCE(crypto_map.delisted_at, EXCLUDED.delisted_at)
+                   END
+               RETURNING (xmax = 0) AS "inserted!";"#,
             data.id as i32,
             data.name,
             data.symbol,
             data.slug,
-            data.rank as i32,
+            data.rank.map(|rank| rank as i32),
             data.is_active,
             data.first_historical_data,
             data.last_historical_data,
             platform_id,
+            data.status,
+            newer,
         )
-        .execute(&pool)
+        .fetch_one(&pool)
         .await?;
+        rows.count(inserted.inserted);
+
+        if let (Some(platform), Some(platform_id)) = (&data.platform, platform_id) {
+            update_token_contract(data.id, platform_id, &platform.token_address, pool.clone())
+                .await?;
+        }
+        update_crypto_alias(data.id, response.status.timestamp, pool.clone()).await?;
     }
-    Ok(())
+    Ok(rows)
+}
+
+/// Records the current name, symbol and slug of the cryptocurrency `id` in `crypto_alias`, as seen
+/// at `seen_at`. If any of them changed, the current row is closed and a new one is opened (along
+/// with the rank at that time, whose changes alone don't open any row).
+/// The values of the first row are assumed to hold since the first historical data. Snapshots not
+/// newer than the current row are ignored.
+async fn update_crypto_alias(
+    id: u32,
+    seen_at: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"UPDATE crypto_alias a SET valid_to = $2
+           FROM crypto_map m
+           WHERE a.id = $1 AND m.id = a.id AND a.valid_to IS NULL AND a.valid_from < $2
+             AND (a.name, a.symbol, a.slug) IS DISTINCT FROM (m.name, m.symbol, m.slug);"#,
+        id as i32,
+        seen_at
+    )
+    .execute(&mut transaction)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO crypto_alias
+           SELECT m.id, m.name, m.symbol, m.slug, m.rank,
+                  CASE WHEN EXISTS (SELECT 1 FROM crypto_alias WHERE id = $1) THEN $2
+                       ELSE LEAST(COALESCE(m.first_historical_data, $2), $2)
+                  END,
+                  NULL
+           FROM crypto_map m
+           WHERE m.id = $1
+             AND NOT EXISTS (SELECT 1 FROM crypto_alias WHERE id = $1 AND valid_to IS NULL);"#,
+        id as i32,
+        seen_at
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await
 }
 
-/// Update the database `crypto_listing` with data from `listing::Response`.
+/// Update the database `crypto_listing` (along with `platform` and `token_contract`) with data from
+/// `listing::Response`. Snapshots already stored (i.e. same `id` and `last_updated`) are left
+/// untouched, while the tags of each cryptocurrency in `crypto_tag` are replaced (if requested
+/// through `aux`). Records without a price are skipped, while missing fields of the others are
+/// stored as `NULL`. Returns the number of snapshots inserted.
 pub async fn update_crypto_listing(
     response: listing::Response,
     pool: PgPool,
-) -> Result<(), sqlx::Error> {
-    for data in &response.data {
+) -> Result<RowCount, sqlx::Error> {
+    insert_crypto_listings(&response.data, pool).await
+}
+
+/// Appends the snapshots `data` to `crypto_listing`, whether they come from the listings or from
+/// the quotes of some cryptocurrencies (see `update_crypto_listing`).
+pub async fn insert_crypto_listings(
+    data: &[listing::Data],
+    pool: PgPool,
+) -> Result<RowCount, sqlx::Error> {
+    let mut rows = RowCount::default();
+    for data in data {
+        let missing_fields = data.quote.usd.missing_fields();
+        if data.quote.usd.price.is_none() {
+            log::warn!("Skipping the listing of id {}: no price available", data.id);
+            continue;
+        }
+        if !missing_fields.is_empty() {
+            log::debug!(
+                "Listing of id {} with missing fields: {}",
+                data.id,
+                missing_fields.join(", ")
+            );
+        }
+
         let mut platform_id = None;
-        if data.platform.is_some() {
-            platform_id = Some(data.id as i32);
+        if let Some(platform) = &data.platform {
+            platform_id = Some(update_token_platform(platform, pool.clone()).await?);
         }
 
-        sqlx::query!(
+        let inserted = sqlx::query!(
             r#"INSERT INTO crypto_listing VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
-                                $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22);"#,
+                                $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)
+               ON CONFLICT (id, last_updated) DO NOTHING;"#,
             data.id as i32,
-            data.num_market_pairs as i32,
-            &data.tags,
+            data.num_market_pairs
+                .map(|num_market_pairs| num_market_pairs as i32),
             data.max_supply,
             data.circulating_supply,
             data.total_supply,
             platform_id,
-            data.cmc_rank as i32,
+            data.cmc_rank.map(|cmc_rank| cmc_rank as i32),
             "USD", // data.quote,
             data.quote.usd.price,
             data.quote.usd.volume_24h,
@@ -78,15 +223,838 @@ pub async fn update_crypto_listing(
         )
         .execute(&pool)
         .await?;
+        rows.inserted += inserted.rows_affected();
+
+        if let (Some(platform), Some(platform_id)) = (&data.platform, platform_id) {
+            update_token_contract(data.id, platform_id, &platform.token_address, pool.clone())
+                .await?;
+        }
+        if let Some(tags) = &data.tags {
+            update_crypto_tags(data.id, tags, pool.clone()).await?;
+        }
+    }
+    Ok(rows)
+}
+
+/// Returns the price of the snapshot preceding each of `data` by `id`, for those having one. The
+/// quarantined snapshots count as well, so that a genuine jump of price quarantined once becomes the
+/// reference of the following snapshots.
+pub async fn get_previous_prices(
+    data: &[listing::Data],
+    pool: PgPool,
+) -> Result<HashMap<u32, BigDecimal>, sqlx::Error> {
+    let ids: Vec<i32> = data.iter().map(|data| data.id as i32).collect();
+    let timestamps: Vec<DateTime<Utc>> = data
+        .iter()
+        .map(|data| data.quote.usd.last_updated)
+        .collect();
+
+    let records = sqlx::query!(
+        r#"SELECT input.id AS "id!", previous.price AS "price!"
+           FROM unnest($1::INTEGER[], $2::timestamptz[]) AS input (id, last_updated)
+           CROSS JOIN LATERAL (
+               SELECT price FROM (
+                   (SELECT price, last_updated FROM crypto_listing
+                    WHERE id = input.id AND last_updated < input.last_updated
+                      AND price IS NOT NULL
+                    ORDER BY last_updated DESC
+                    LIMIT 1)
+                   UNION ALL
+                   (SELECT price, last_updated FROM crypto_listing_quarantine
+                    WHERE id = input.id AND last_updated < input.last_updated
+                      AND price IS NOT NULL
+                    ORDER BY last_updated DESC
+                    LIMIT 1)
+               ) AS snapshot
+               ORDER BY last_updated DESC
+               LIMIT 1
+           ) AS previous;"#,
+        &ids,
+        &timestamps
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| (record.id as u32, record.price))
+        .collect())
+}
+
+/// Stores the listings that failed the validation in `crypto_listing_quarantine` along with the
+/// reasons, on behalf of the ingestion run `run` (if any). Records already quarantined (i.e. same
+/// `id` and `last_updated`) are left untouched. Returns the number of records inserted.
+pub async fn quarantine_crypto_listings(
+    invalid: &[Invalid],
+    run: Option<i64>,
+    pool: PgPool,
+) -> Result<u64, sqlx::Error> {
+    let mut inserted = 0;
+    for invalid in invalid {
+        let data = &invalid.data;
+        let result = sqlx::query!(
+            r#"INSERT INTO crypto_listing_quarantine (id, symbol, last_updated, price,
+                   previous_price, circulating_supply, total_supply, max_supply, reasons,
+                   ingest_run)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+               ON CONFLICT (id, last_updated) DO NOTHING;"#,
+            data.id as i32,
+            data.symbol,
+            data.quote.usd.last_updated,
+            data.quote.usd.price,
+            invalid.previous_price,
+            data.circulating_supply,
+            data.total_supply,
+            data.max_supply,
+            &invalid.reasons(),
+            run
+        )
+        .execute(&pool)
+        .await?;
+        inserted += result.rows_affected();
+    }
+
+    Ok(inserted)
+}
+
+/// Replace the tags of the cryptocurrency `id` in `crypto_tag`.
+async fn update_crypto_tags(id: u32, tags: &[String], pool: PgPool) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!("DELETE FROM crypto_tag WHERE id = $1;", id as i32)
+        .execute(&mut transaction)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO crypto_tag SELECT $1, unnest($2::TEXT[]) ON CONFLICT DO NOTHING;",
+        id as i32,
+        tags
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await
+}
+
+/// Update the database `platform` with the `platform` on which a token was issued, returning its id.
+async fn update_token_platform(platform: &Platform, pool: PgPool) -> Result<i32, sqlx::Error> {
+    update_platform(
+        &platform.name,
+        platform.id,
+        &platform.symbol,
+        &platform.slug,
+        pool,
+    )
+    .await
+}
+
+/// Update the database `platform` with the chain `name`, whose native coin is `coin_id`, returning
+/// its id.
+async fn update_platform(
+    name: &str,
+    coin_id: u32,
+    symbol: &str,
+    slug: &str,
+    pool: PgPool,
+) -> Result<i32, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"INSERT INTO platform (name, coin_id, symbol, slug) VALUES ($1, $2, $3, $4)
+           ON CONFLICT (name) DO UPDATE
+           SET coin_id = EXCLUDED.coin_id, symbol = EXCLUDED.symbol, slug = EXCLUDED.slug
+           RETURNING id;"#,
+        name,
+        coin_id as i32,
+        symbol,
+        slug,
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(record.id)
+}
+
+/// Update the database `token_contract` with the `contract_address` of the cryptocurrency `id` on
+/// the platform `platform_id`. Empty addresses are ignored.
+async fn update_token_contract(
+    id: u32,
+    platform_id: i32,
+    contract_address: &str,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    if contract_address.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO token_contract VALUES ($1, $2, $3)
+           ON CONFLICT (platform_id, contract_address) DO UPDATE SET id = EXCLUDED.id;"#,
+        id as i32,
+        platform_id,
+        contract_address,
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Update the databases `platform` and `token_contract` with the contracts of every chain on which
+/// each cryptocurrency of `info::Response` is deployed.
+pub async fn update_token_contracts(
+    response: &info::Response,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    for data in response.data.values() {
+        for contract in &data.contract_address {
+            let coin = &contract.platform.coin;
+            let platform_id = update_platform(
+                &contract.platform.name,
+                coin.id,
+                &coin.symbol,
+                &coin.slug,
+                pool.clone(),
+            )
+            .await?;
+            update_token_contract(
+                data.id,
+                platform_id,
+                &contract.contract_address,
+                pool.clone(),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the ids in `crypto_map` of the tokens, i.e. the cryptocurrencies issued on a platform.
+pub async fn get_token_ids(pool: PgPool) -> Result<Vec<u32>, sqlx::Error> {
+    let records = sqlx::query!("SELECT id FROM crypto_map WHERE platform IS NOT NULL ORDER BY id;")
+        .fetch_all(&pool)
+        .await?;
+    Ok(records.into_iter().map(|record| record.id as u32).collect())
+}
+
+/// Update the database `category` with data from `categories::Response`.
+pub async fn update_categories(
+    response: &categories::Response,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    for data in &response.data {
+        sqlx::query!(
+            r#"INSERT INTO category VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+               ON CONFLICT (id) DO UPDATE
+               SET name = EXCLUDED.name, title = EXCLUDED.title,
+                   description = EXCLUDED.description, num_tokens = EXCLUDED.num_tokens,
+                   avg_price_change = EXCLUDED.avg_price_change,
+                   market_cap = EXCLUDED.market_cap,
+                   market_cap_change = EXCLUDED.market_cap_change, volume = EXCLUDED.volume,
+                   volume_change = EXCLUDED.volume_change,
+                   last_updated = EXCLUDED.last_updated;"#,
+            data.id,
+            data.name,
+            data.title,
+            data.description,
+            data.num_tokens as i32,
+            data.avg_price_change,
+            data.market_cap,
+            data.market_cap_change,
+            data.volume,
+            data.volume_change,
+            data.last_updated,
+        )
+        .execute(&pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Replace the members of the category in `category::Response` in `crypto_category`.
+/// Cryptocurrencies that are not present in `crypto_map` are skipped.
+pub async fn update_category_members(
+    response: &category::Response,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    let ids: Vec<i32> = response
+        .data
+        .coins
+        .iter()
+        .map(|coin| coin.id as i32)
+        .collect();
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM crypto_category WHERE category_id = $1;",
+        response.data.id
+    )
+    .execute(&mut transaction)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO crypto_category
+           SELECT $1, id FROM crypto_map WHERE id = ANY($2)
+           ON CONFLICT DO NOTHING;"#,
+        response.data.id,
+        &ids
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await
+}
+
+/// Update the database `crypto_listing` with the historical quotes in `quotes_historical::Response`.
+/// Only the fields provided by the historical endpoint are filled in, and quotes without a price
+/// are skipped.
+pub async fn update_quotes_historical(
+    response: &quotes_historical::Response,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    for data in response.data.values() {
+        for quote in &data.quotes {
+            let point = &quote.quote.usd;
+            if point.price.is_none() {
+                log::warn!(
+                    "Skipping the quote of id {} at {}: no price available",
+                    data.id,
+                    quote.timestamp
+                );
+                continue;
+            }
+            sqlx::query!(
+                r#"INSERT INTO crypto_listing (id, circulating_supply, total_supply, quote, price,
+                                               volume_24h, market_cap, last_updated)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                   ON CONFLICT (id, last_updated) DO NOTHING;"#,
+                data.id as i32,
+                point.circulating_supply,
+                point.total_supply,
+                "USD",
+                point.price,
+                point.volume_24h,
+                point.market_cap,
+                quote.timestamp,
+            )
+            .execute(&pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Update the database `crypto_ohlcv` with data from `ohlcv_historical::Response`. Periods without
+/// a close price are skipped.
+pub async fn update_ohlcv_historical(
+    response: &ohlcv_historical::Response,
+    time_period: &str,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    for data in response.data.values() {
+        for quote in &data.quotes {
+            let ohlcv = &quote.quote.usd;
+            if ohlcv.close.is_none() {
+                log::warn!(
+                    "Skipping the OHLCV of id {} at {}: no close price available",
+                    data.id,
+                    quote.time_open
+                );
+                continue;
+            }
+            sqlx::query!(
+                r#"INSERT INTO crypto_ohlcv VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                   ON CONFLICT (id, time_period, quote, time_open) DO UPDATE
+                   SET time_close = EXCLUDED.time_close, open = EXCLUDED.open,
+                       high = EXCLUDED.high, low = EXCLUDED.low, close = EXCLUDED.close,
+                       volume = EXCLUDED.volume, market_cap = EXCLUDED.market_cap;"#,
+                data.id as i32,
+                time_period,
+                "USD",
+                quote.time_open,
+                quote.time_close,
+                ohlcv.open,
+                ohlcv.high,
+                ohlcv.low,
+                ohlcv.close,
+                ohlcv.volume,
+                ohlcv.market_cap,
+            )
+            .execute(&pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Update the database `crypto_trending` with the snapshot of `feed` in `trending::Response`.
+/// Cryptocurrencies that are not present in `crypto_map` are skipped (see
+/// `App::resolve_crypto_map`).
+pub async fn update_crypto_trending(
+    response: &trending::Response,
+    feed: &str,
+    time_period: &str,
+    fetched_at: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    for (rank, data) in response.data.iter().enumerate() {
+        let changes = &data.quote.usd;
+        sqlx::query!(
+            r#"INSERT INTO crypto_trending
+               SELECT $1, $2, $3, $4, id, $6, $7, $8, $9 FROM crypto_map WHERE id = $5
+               ON CONFLICT DO NOTHING;"#,
+            feed,
+            time_period,
+            fetched_at,
+            rank as i32 + 1,
+            data.id as i32,
+            "USD",
+            changes.price,
+            trending::percent_change(changes, time_period),
+            changes.last_updated,
+        )
+        .execute(&pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if the cryptocurrency `id` is present in `crypto_map`.
+pub async fn crypto_map_contains(id: u32, pool: PgPool) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT EXISTS (SELECT 1 FROM crypto_map WHERE id = $1) AS "exists!";"#,
+        id as i32
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(record.exists)
+}
+
+/// Returns the ids among `ids` that are not present in `crypto_map`.
+pub async fn get_missing_crypto_map_ids(
+    ids: &[u32],
+    pool: PgPool,
+) -> Result<Vec<u32>, sqlx::Error> {
+    let ids: Vec<i32> = ids.iter().map(|&id| id as i32).collect();
+    let records = sqlx::query!(
+        r#"SELECT ids.id AS "id!" FROM unnest($1::INTEGER[]) AS ids(id)
+           WHERE NOT EXISTS (SELECT 1 FROM crypto_map WHERE crypto_map.id = ids.id);"#,
+        &ids
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(records.into_iter().map(|record| record.id as u32).collect())
+}
+
+/// Returns the last timestamp stored by the backfill of `endpoint` for the cryptocurrency `id`.
+pub async fn get_backfill_progress(
+    id: u32,
+    endpoint: &str,
+    pool: PgPool,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let record = sqlx::query!(
+        "SELECT last_timestamp FROM backfill_progress WHERE id = $1 AND endpoint = $2;",
+        id as i32,
+        endpoint
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(record.map(|record| record.last_timestamp))
+}
+
+/// Records `last_timestamp` as the progress of the backfill of `endpoint` for the cryptocurrency
+/// `id`.
+pub async fn set_backfill_progress(
+    id: u32,
+    endpoint: &str,
+    last_timestamp: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO backfill_progress VALUES ($1, $2, $3)
+           ON CONFLICT (id, endpoint) DO UPDATE SET last_timestamp = EXCLUDED.last_timestamp;"#,
+        id as i32,
+        endpoint,
+        last_timestamp
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Records in `key_usage` the plan limits and usage of the API key from `key_info::Response`.
+pub async fn update_key_usage(
+    response: &key_info::Response,
+    fetched_at: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    let (plan, usage) = (&response.data.plan, &response.data.usage);
+    sqlx::query!(
+        r#"INSERT INTO key_usage VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+           ON CONFLICT DO NOTHING;"#,
+        fetched_at,
+        plan.credit_limit_daily.map(|limit| limit as i32),
+        plan.credit_limit_monthly as i32,
+        plan.credit_limit_monthly_reset_timestamp,
+        plan.rate_limit_minute as i32,
+        usage.current_minute.requests_made as i32,
+        usage.current_minute.requests_left as i32,
+        usage.current_day.credits_used as i32,
+        usage.current_day.credits_left.map(|left| left as i32),
+        usage.current_month.credits_used as i32,
+        response.data.monthly_credits_left() as i32,
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Stores a snapshot of the global market metrics in `global_metrics`, unless already stored.
+pub async fn update_global_metrics(
+    data: &global_metrics::Data,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    let usd = &data.quote.usd;
+    sqlx::query!(
+        r#"INSERT INTO global_metrics VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12,
+                                              $13, $14, $15, $16, $17)
+           ON CONFLICT DO NOTHING;"#,
+        data.last_updated,
+        data.active_cryptocurrencies as i32,
+        data.total_cryptocurrencies.map(|total| total as i32),
+        data.active_market_pairs as i32,
+        data.active_exchanges as i32,
+        data.total_exchanges.map(|total| total as i32),
+        data.btc_dominance,
+        data.eth_dominance,
+        data.defi_volume_24h,
+        data.defi_market_cap,
+        data.stablecoin_volume_24h,
+        data.stablecoin_market_cap,
+        data.derivatives_volume_24h,
+        usd.total_market_cap,
+        usd.total_volume_24h,
+        usd.altcoin_market_cap,
+        usd.altcoin_volume_24h,
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Records in `schema_drift` the `drift` detected in a response of `endpoint`. Returns the drift
+/// that was seen for the first time.
+pub async fn update_schema_drift(
+    endpoint: &str,
+    drift: &Drift,
+    seen_at: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<Drift, sqlx::Error> {
+    let mut new = Drift::default();
+    let kinds = [
+        ("unknown", &drift.unknown, &mut new.unknown),
+        ("missing", &drift.missing, &mut new.missing),
+    ];
+
+    for (kind, paths, new_paths) in kinds {
+        for path in paths {
+            let record = sqlx::query!(
+                r#"INSERT INTO schema_drift VALUES ($1, $2, $3, $4, $4, 1)
+                   ON CONFLICT (endpoint, kind, path) DO UPDATE
+                   SET last_seen = EXCLUDED.last_seen,
+                       occurrences = schema_drift.occurrences + 1
+                   RETURNING occurrences;"#,
+                endpoint,
+                kind,
+                path,
+                seen_at
+            )
+            .fetch_one(&pool)
+            .await?;
+
+            if record.occurrences == 1 {
+                new_paths.insert(path.clone());
+            }
+        }
     }
+
+    Ok(new)
+}
+
+/// Rows written (or left out by the validation) and call credits consumed by an ingestion run so
+/// far.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IngestProgress {
+    pub rows: RowCount,
+    /// Records dropped by the validation or malformed.
+    pub rejected: u32,
+    pub quarantined: u32,
+    pub credits_used: u32,
+}
+
+/// Records in `ingest_run` the start of a run fetching `endpoint` with the query `params`, and
+/// returns its `id`.
+pub async fn start_ingest_run(
+    endpoint: &str,
+    params: &str,
+    pool: PgPool,
+) -> Result<i64, sqlx::Error> {
+    let record = sqlx::query!(
+        "INSERT INTO ingest_run (endpoint, params, started_at) VALUES ($1, $2, now()) RETURNING id;",
+        endpoint,
+        params
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(record.id)
+}
+
+/// Records in `ingest_run` the end of the run `id`, along with its `progress` and `error` (if it
+/// failed).
+pub async fn finish_ingest_run(
+    id: i64,
+    progress: &IngestProgress,
+    error: Option<&str>,
+    pool: PgPool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"UPDATE ingest_run
+           SET finished_at = now(), rows_inserted = $2, rows_updated = $3, credits_used = $4,
+               error = $5, rows_rejected = $6, rows_quarantined = $7
+           WHERE id = $1;"#,
+        id,
+        progress.rows.inserted as i32,
+        progress.rows.updated as i32,
+        progress.credits_used as i32,
+        error,
+        progress.rejected as i32,
+        progress.quarantined as i32
+    )
+    .execute(&pool)
+    .await?;
+
     Ok(())
 }
 
+/// Returns the most recent runs in `ingest_run`, newest first.
+pub async fn get_ingest_runs(limit: i64, pool: PgPool) -> Result<Vec<IngestRun>, sqlx::Error> {
+    sqlx::query_as!(
+        IngestRun,
+        r#"SELECT id, endpoint, params, started_at, finished_at, rows_inserted, rows_updated,
+                  rows_rejected, rows_quarantined, credits_used, error
+           FROM ingest_run
+           ORDER BY started_at DESC, id DESC
+           LIMIT $1;"#,
+        limit
+    )
+    .fetch_all(&pool)
+    .await
+}
+
+/// Row of `ingest_run`.
+#[derive(Debug)]
+pub struct IngestRun {
+    pub id: i64,
+    pub endpoint: String,
+    pub params: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub rows_inserted: i32,
+    pub rows_updated: i32,
+    pub rows_rejected: i32,
+    pub rows_quarantined: i32,
+    pub credits_used: i32,
+    pub error: Option<String>,
+}
+
+/// Creates the monthly partition of `crypto_listing` holding `month`, moving there the snapshots of
+/// that month stored in the default partition. Returns whether the partition was missing.
+pub async fn create_listing_partition(
+    month: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT create_crypto_listing_partition($1) AS "created!";"#,
+        month
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(record.created)
+}
+
+/// Returns the names of the monthly partitions of `crypto_listing` (e.g. `crypto_listing_2022_01`),
+/// oldest first.
+pub async fn get_listing_partitions(pool: PgPool) -> Result<Vec<String>, sqlx::Error> {
+    let records = sqlx::query!(
+        r#"SELECT c.relname::TEXT AS "name!"
+           FROM pg_inherits i
+           JOIN pg_class c ON c.oid = i.inhrelid
+           WHERE i.inhparent = 'crypto_listing'::regclass
+             AND c.relname ~ '^crypto_listing_[0-9]{4}_[0-9]{2}$'
+           ORDER BY c.relname;"#
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(records.into_iter().map(|record| record.name).collect())
+}
+
+/// Returns the months (in UTC) of the snapshots stored in the default partition of
+/// `crypto_listing`, i.e. without a partition of their own yet.
+pub async fn get_unpartitioned_months(pool: PgPool) -> Result<Vec<DateTime<Utc>>, sqlx::Error> {
+    let records = sqlx::query!(
+        r#"SELECT DISTINCT
+               date_trunc('month', last_updated AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS "month!"
+           FROM crypto_listing_default
+           ORDER BY 1;"#
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(records.into_iter().map(|record| record.month).collect())
+}
+
+/// Drops the partition `name` of `crypto_listing`, along with its snapshots.
+pub async fn drop_listing_partition(name: &str, pool: PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DROP TABLE \"{}\";", name.replace('"', "\"\"")))
+        .execute(&pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Aggregates the snapshots of `crypto_listing` updated during `[since, until)` per hour or day (see
+/// `Resolution`), replacing the aggregates of the same buckets. Hence `since` must start a bucket.
+/// Returns the number of aggregates written.
+pub async fn rollup_crypto_listings(
+    resolution: Resolution,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<u64, sqlx::Error> {
+    // The table and the unit are never user input.
+    let query = format!(
+        r#"INSERT INTO {table}
+           SELECT id,
+                  date_trunc('{unit}', last_updated AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                  (array_agg(price ORDER BY last_updated))[1],
+                  max(price),
+                  min(price),
+                  (array_agg(price ORDER BY last_updated DESC))[1],
+                  avg(volume_24h),
+                  (array_agg(market_cap ORDER BY last_updated DESC)
+                      FILTER (WHERE market_cap IS NOT NULL))[1],
+                  count(*)
+           FROM crypto_listing
+           WHERE price IS NOT NULL AND last_updated >= $1 AND last_updated < $2
+           GROUP BY 1, 2
+           ON CONFLICT (id, bucket) DO UPDATE
+           SET open = EXCLUDED.open, high = EXCLUDED.high, low = EXCLUDED.low,
+               close = EXCLUDED.close, volume_24h = EXCLUDED.volume_24h,
+               market_cap = EXCLUDED.market_cap, snapshots = EXCLUDED.snapshots;"#,
+        table = resolution.table(),
+        unit = resolution.unit()
+    );
+    let result = sqlx::query(&query)
+        .bind(since)
+        .bind(until)
+        .execute(&pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
 /// **Warning:** This function *empties all the tables* in the database.
 // FIXME: Consider making this function private and the integration tests that use it, unit tests.
 pub async fn clear_all_tables(pool: PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query!("TRUNCATE TABLE crypto_platform CASCADE;")
-        .execute(&pool)
+    sqlx::query!(
+        "TRUNCATE TABLE platform, category, key_usage, schema_drift, ingest_run, global_metrics, \
+         crypto_listing_quarantine, crypto_listing_hourly, crypto_listing_daily, \
+         crypto_listing_latest CASCADE;"
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Summary of the contents of the local cache.
+#[derive(Debug)]
+pub struct CacheStatus {
+    /// Number of cryptocurrencies in `crypto_map`.
+    pub cryptocurrencies: i64,
+    /// Number of snapshots in `crypto_listing`.
+    pub listings: i64,
+    /// Timestamp of the most recent listing.
+    pub last_listing: Option<DateTime<Utc>>,
+    /// When the usage of the API key was last fetched, along with the call credits left this month.
+    pub key_usage: Option<(DateTime<Utc>, i32)>,
+}
+
+/// Returns a summary of the contents of the local cache.
+pub async fn get_cache_status(pool: PgPool) -> Result<CacheStatus, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT (SELECT COUNT(*) FROM crypto_map) AS "cryptocurrencies!",
+                  (SELECT COUNT(*) FROM crypto_listing) AS "listings!",
+                  (SELECT MAX(last_updated) FROM crypto_listing) AS last_listing;"#
+    )
+    .fetch_one(&pool)
+    .await?;
+    let key_usage = sqlx::query!(
+        "SELECT fetched_at, credits_left_month FROM key_usage ORDER BY fetched_at DESC LIMIT 1;"
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(CacheStatus {
+        cryptocurrencies: record.cryptocurrencies,
+        listings: record.listings,
+        last_listing: record.last_listing,
+        key_usage: key_usage.map(|usage| (usage.fetched_at, usage.credits_left_month)),
+    })
+}
+
+/// Tries to acquire the session-level advisory lock (`namespace`, `key`) on `conn` without waiting,
+/// and returns whether it was acquired. The lock is held until the session ends, e.g. when the
+/// process dies.
+pub async fn try_advisory_lock(
+    namespace: i32,
+    key: i32,
+    conn: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT pg_try_advisory_lock($1, $2);")
+        .bind(namespace)
+        .bind(key)
+        .fetch_one(conn)
+        .await
+}
+
+/// Returns whether the session of `conn` holds the advisory lock (`namespace`, `key`).
+pub async fn holds_advisory_lock(
+    namespace: i32,
+    key: i32,
+    conn: &mut PgConnection,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT EXISTS (SELECT 1 FROM pg_locks
+                          WHERE locktype = 'advisory' AND pid = pg_backend_pid() AND granted
+                            AND classid = $1::int4::oid AND objid = $2::int4::oid
+                            AND objsubid = 2);"#,
+    )
+    .bind(namespace)
+    .bind(key)
+    .fetch_one(conn)
+    .await
+}
+
+/// Releases all the session-level advisory locks held by the session of `conn`.
+pub async fn release_advisory_locks(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_unlock_all();")
+        .execute(conn)
         .await?;
 
     Ok(())
@@ -0,0 +1,117 @@
+//! Module that reads dumps of responses of the CoinMarketCap API (e.g. archived by a previous
+//! deployment), so they can be imported without consuming any call credit.
+//!
+//! A dump is either a single file or a directory, searched recursively for files with the extensions
+//! `.json`, `.ndjson` or `.jsonl`, optionally gzipped (`.gz`). Every file may hold a bundle of many
+//! snapshots, i.e. a stream of whitespace-separated responses such as one response per line.
+
+use serde_json::de::IoRead;
+use serde_json::{StreamDeserializer, Value};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::coin_market::CmcError;
+
+/// Extensions of the files read from a directory, without the optional `.gz`.
+const EXTENSIONS: [&str; 3] = ["json", "ndjson", "jsonl"];
+
+/// Magic bytes at the start of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Endpoint whose response is stored in a dump to import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dump {
+    /// A response of `/v1/cryptocurrency/map`.
+    Map,
+    /// A response of `/v1/cryptocurrency/listings/latest`.
+    Listing,
+}
+
+impl Dump {
+    /// Guesses the endpoint of a snapshot from its first record, since only listings have a
+    /// `quote`. Returns `None` if it isn't a response of a known endpoint or has no records.
+    pub fn detect(snapshot: &Value) -> Option<Dump> {
+        let first = snapshot.get("data")?.as_array()?.first()?;
+
+        if first.get("quote").is_some() {
+            Some(Dump::Listing)
+        } else if first.get("id").is_some() && first.get("slug").is_some() {
+            Some(Dump::Map)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for Dump {
+    type Err = CmcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "map" => Ok(Dump::Map),
+            "listing" => Ok(Dump::Listing),
+            other => Err(CmcError::InvalidArgument(format!(
+                "invalid dump `{}`, expected `map` or `listing`",
+                other
+            ))),
+        }
+    }
+}
+
+/// Number of snapshots of a dump imported by `App::import`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub maps: usize,
+    pub listings: usize,
+    /// Snapshots skipped because they are invalid or of an unknown endpoint.
+    pub failed: usize,
+}
+
+/// Stream of the snapshots stored in a file.
+pub type Snapshots = StreamDeserializer<'static, IoRead<Box<dyn Read>>, Value>;
+
+/// Returns the files of the dump at `path`, sorted by path so that archived snapshots (usually
+/// named after their timestamp) are replayed in order.
+pub fn dump_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(dump_files(&path)?);
+        } else if is_dump_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn is_dump_file(path: &Path) -> bool {
+    let path = match path.extension() {
+        Some(extension) if extension == "gz" => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension))
+}
+
+/// Returns the stream of snapshots stored in the file at `path`, which is decompressed first if
+/// gzipped (regardless of its extension).
+pub fn read_snapshots(path: &Path) -> io::Result<Snapshots> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+
+    Ok(serde_json::Deserializer::from_reader(reader).into_iter())
+}
@@ -0,0 +1,94 @@
+//! Elect a leader per job among the replicas of the daemon, so that each job runs on a single
+//! replica at a time (e.g. the listings are not fetched, nor the call credits used, twice).
+//!
+//! Every replica keeps a dedicated connection to the database, on which it holds a session-level
+//! advisory lock (see `pg_try_advisory_lock`) for each job it leads. Once a replica acquires the
+//! lock of a job, it leads it until it shuts down or loses its connection. The other replicas stand
+//! by: whenever the job is due, they try to acquire its lock and skip the run if it's taken. Since
+//! PostgreSQL releases the locks of a session when it ends, a standby takes over a job the next
+//! time it's due after the leader died.
+
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{ConnectOptions, Connection, PgConnection};
+use std::collections::HashSet;
+
+use crate::configuration::{DbConfig, LeaderConfig};
+use crate::database;
+use crate::scheduler::Job;
+
+/// Jobs led by this replica, along with the connection holding their locks.
+pub struct Leadership {
+    namespace: i32,
+    options: PgConnectOptions,
+    connection: Option<PgConnection>,
+    led: HashSet<Job>,
+    /// Jobs led by another replica, only kept to log the changes of leader.
+    standby: HashSet<Job>,
+}
+
+impl Leadership {
+    pub fn new(config: &LeaderConfig, database: &DbConfig) -> Self {
+        Self {
+            namespace: config.lock_namespace,
+            options: database.with_db(),
+            connection: None,
+            led: HashSet::new(),
+            standby: HashSet::new(),
+        }
+    }
+
+    /// Returns whether this replica leads `job`, trying to acquire its lock if it doesn't yet.
+    pub async fn lead(&mut self, job: Job) -> Result<bool, sqlx::Error> {
+        let result = self.try_lead(job).await;
+        if result.is_err() {
+            // The locks are released along with the session, so they must be acquired again.
+            self.connection = None;
+            for job in self.led.drain() {
+                log::warn!("Lost the lead of job `{}`", job.as_str());
+            }
+        }
+
+        result
+    }
+
+    async fn try_lead(&mut self, job: Job) -> Result<bool, sqlx::Error> {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => self.connection.insert(self.options.connect().await?),
+        };
+        let key = job as i32;
+
+        if self.led.contains(&job) {
+            if database::holds_advisory_lock(self.namespace, key, connection).await? {
+                return Ok(true);
+            }
+            self.led.remove(&job);
+            log::warn!("Lost the lead of job `{}`", job.as_str());
+        }
+
+        let leads = database::try_advisory_lock(self.namespace, key, connection).await?;
+        if leads {
+            self.led.insert(job);
+            self.standby.remove(&job);
+            log::info!("Leading job `{}`", job.as_str());
+        } else if self.standby.insert(job) {
+            log::info!(
+                "Standing by for job `{}`, led by another replica",
+                job.as_str()
+            );
+        }
+
+        Ok(leads)
+    }
+
+    /// Releases the locks of all the jobs, so that the standbys take over right away.
+    pub async fn resign(mut self) {
+        if let Some(mut connection) = self.connection.take() {
+            // Closing the connection would release the locks too, but only once the server notices.
+            if let Err(error) = database::release_advisory_locks(&mut connection).await {
+                log::warn!("Failed to release the lead of the jobs: {}", error);
+            }
+            let _ = connection.close().await;
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod cassette;
 pub mod coin_market;
 pub mod configuration;
 pub mod database;
+pub mod import;
+pub mod leader;
+pub mod poller;
+pub mod retention;
+pub mod scheduler;
+pub mod schema_drift;
+pub mod validation;
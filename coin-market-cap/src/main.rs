@@ -1,24 +1,366 @@
-use coin_market_cap::coin_market::{App, CmcError};
-use std::time::Duration;
+use chrono::prelude::*;
+use clap::{Parser, Subcommand};
+use coin_market_cap::coin_market::{map::ListingStatus, App, CmcError};
+use coin_market_cap::import::Dump;
+use coin_market_cap::leader::Leadership;
+use coin_market_cap::poller::{Poller, Shutdown};
+use coin_market_cap::scheduler::{Job, Scheduler};
+use std::path::{Path, PathBuf};
 
 // Use `jemalloc` only for musl-64 bits platforms
 #[cfg(all(target_env = "musl", target_pointer_width = "64"))]
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
+/// Fetches information about cryptocurrencies from the CoinMarketCap API and caches it locally
+/// using a migrated PostgreSQL database.
+#[derive(Parser)]
+#[clap(version, about)]
+struct Cli {
+    /// Runs as a daemon with the default settings if omitted.
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the scheduled jobs, e.g. periodically fetches the latest listings.
+    Run {
+        /// Seconds between two fetches of the listings, overriding the `schedule` section of the
+        /// configuration.
+        #[clap(long)]
+        interval: Option<u64>,
+        #[clap(flatten)]
+        listing: ListingArgs,
+    },
+    /// Fetches the latest listings (and trending feeds, if scheduled) once.
+    FetchOnce {
+        #[clap(flatten)]
+        listing: ListingArgs,
+    },
+    /// Backfills the history of some cryptocurrencies, e.g. `backfill 1,1027 2021-01-01`.
+    Backfill {
+        /// Comma-separated list of CoinMarketCap ids.
+        #[clap(parse(try_from_str = parse_ids))]
+        ids: Ids,
+        /// Either an RFC 3339 timestamp or a date (`YYYY-MM-DD`).
+        #[clap(parse(try_from_str = parse_timestamp))]
+        time_start: DateTime<Utc>,
+        /// Either an RFC 3339 timestamp or a date (`YYYY-MM-DD`), now by default.
+        #[clap(parse(try_from_str = parse_timestamp))]
+        time_end: Option<DateTime<Utc>>,
+    },
+    /// Fetches the whole map of cryptocurrencies, e.g. `map active,inactive`.
+    Map {
+        /// Comma-separated list of listing statuses (all of them by default).
+        #[clap(use_value_delimiter = true)]
+        listing_status: Vec<ListingStatus>,
+    },
+    /// Fetches the contracts of some tokens on every chain on which they are deployed.
+    Contracts {
+        /// Comma-separated list of CoinMarketCap ids (all the tokens in the map by default).
+        #[clap(parse(try_from_str = parse_ids))]
+        ids: Option<Ids>,
+    },
+    /// Fetches all the coin categories and their cryptocurrencies.
+    Categories,
+    /// Imports a dump of responses without consuming any call credit, e.g. to seed a database or
+    /// replay archived data.
+    Import {
+        /// A JSON or NDJSON file (optionally gzipped), or a directory of them.
+        path: PathBuf,
+        /// Endpoint of the responses, either `map` or `listing` (detected by default).
+        #[clap(long)]
+        dump: Option<Dump>,
+    },
+    /// Rolls up the history of the listings into hourly and daily aggregates, and drops the
+    /// snapshots older than the retention.
+    Retention,
+    /// Creates the database if missing and runs the pending migrations.
+    Migrate,
+    /// Reports the usage of the API key.
+    Credits,
+    /// Reports the state of the database schema and the local cache.
+    Status,
+}
+
+/// Listings fetched by `run` and `fetch-once`.
+#[derive(clap::Args)]
+struct ListingArgs {
+    /// Offset (1-based) of the first cryptocurrency.
+    #[clap(long, default_value = "1")]
+    start: u32,
+    /// Number of cryptocurrencies.
+    #[clap(long, default_value = "100")]
+    limit: u32,
+    /// Currency in which the market data is quoted.
+    #[clap(long, default_value = "USD")]
+    convert: String,
+}
+
+impl Default for ListingArgs {
+    fn default() -> Self {
+        Self {
+            start: 1,
+            limit: 100,
+            convert: "USD".to_string(),
+        }
+    }
+}
+
+/// Comma-separated list of CoinMarketCap ids.
+type Ids = Vec<u32>;
+
 /// Entry point of the `coin-market-cap` application, which is responsible for periodically fetching
 /// information about cryptocurrencies from the CoinMarketCap API and then caching it locally using
-/// a migrated PostgreSQL database.
-
-// A more correct return type would be `Result<!, CmcError>`, but the *never* type is still
-// experimental.
+/// a migrated PostgreSQL database. See `coin-market-cap help` for the available subcommands.
 #[tokio::main]
 async fn main() -> Result<(), CmcError> {
-    let duration = Duration::from_secs(5 * 60);
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
     let app = App::new();
 
-    loop {
-        app.fetch_crypto_data(1, 100, "USD").await?;
-        tokio::time::sleep(duration).await;
+    let command = cli.command.unwrap_or(Command::Run {
+        interval: None,
+        listing: ListingArgs::default(),
+    });
+    // The schema is only checked (and migrated, if enabled) before the commands that use it.
+    if !matches!(command, Command::Migrate | Command::Status) {
+        app.bootstrap().await?;
+    }
+
+    match command {
+        Command::Run { interval, listing } => run(&app, interval, &listing).await,
+        Command::FetchOnce { listing } => fetch_once(&app, &listing).await,
+        Command::Backfill {
+            ids,
+            time_start,
+            time_end,
+        } => {
+            let time_end = time_end.unwrap_or_else(Utc::now);
+            let credits = app.backfill(&ids, time_start, time_end).await?;
+            log::info!("Backfill finished using {} credits", credits);
+            Ok(())
+        }
+        Command::Map { listing_status } => fetch_map(&app, listing_status).await,
+        Command::Contracts { ids } => app.fetch_token_contracts(&ids.unwrap_or_default()).await,
+        Command::Categories => app.fetch_categories().await,
+        Command::Import { path, dump } => import(&app, &path, dump).await,
+        Command::Retention => app.apply_retention().await.map(|_| ()),
+        Command::Migrate => app.migrate().await,
+        Command::Credits => credits(&app).await,
+        Command::Status => status(&app).await,
+    }
+}
+
+/// Runs the jobs of the `schedule` section of the configuration, e.g. fetching the latest listings
+/// every 5 minutes and the usage of the API key every hour. Failed jobs are retried or skipped (see
+/// `poller`), and the daemon shuts down gracefully on SIGTERM or SIGINT once the current job is
+/// finished. If enabled, each job only runs on the replica leading it (see `leader`).
+async fn run(app: &App, interval: Option<u64>, listing: &ListingArgs) -> Result<(), CmcError> {
+    let config = app.config();
+    let mut schedule = config.schedule.clone();
+    if let Some(interval) = interval {
+        schedule.listing.interval = Some(interval);
+        schedule.listing.cron = None;
+    }
+    if schedule.quotes.enabled && config.watchlist.ids.is_empty() {
+        log::warn!("The job `quotes` is enabled, but the `watchlist` is empty");
+    }
+    let mut scheduler = Scheduler::new(&schedule, Utc::now())?;
+    let mut leadership = config
+        .leader_election
+        .enabled
+        .then(|| Leadership::new(&config.leader_election, &config.database));
+    let mut shutdown = Shutdown::on_signal();
+    let mut poller = Poller::new(config.poller.clone(), shutdown.clone());
+
+    while let Some((job, due_at)) = scheduler.next() {
+        let wait = (due_at - Utc::now()).to_std().unwrap_or_default();
+        if shutdown.sleep(wait).await {
+            break;
+        }
+        if let Some(leadership) = &mut leadership {
+            match leadership.lead(job).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    scheduler.reschedule(job, Utc::now());
+                    continue;
+                }
+                Err(error) => {
+                    log::warn!(
+                        "Skipping job `{}`, its leader can't be elected: {}",
+                        job.as_str(),
+                        error
+                    );
+                    scheduler.reschedule(job, Utc::now());
+                    continue;
+                }
+            }
+        }
+        let name = format!("Job `{}`", job.as_str());
+        poller.cycle(&name, || run_job(app, job, listing)).await?;
+        scheduler.reschedule(job, Utc::now());
+    }
+    if let Some(leadership) = leadership {
+        leadership.resign().await;
+    }
+    log::info!("Shut down gracefully");
+
+    Ok(())
+}
+
+/// Runs a scheduled `job` once.
+async fn run_job(app: &App, job: Job, listing: &ListingArgs) -> Result<(), CmcError> {
+    match job {
+        Job::KeyInfo => app.fetch_key_info().await.map(|_| ()),
+        Job::Map => fetch_map(app, Vec::new()).await,
+        Job::Listing => {
+            app.fetch_crypto_data(listing.start, listing.limit, &listing.convert)
+                .await
+        }
+        Job::Trending => app.fetch_trending().await,
+        Job::Quotes => {
+            app.fetch_watchlist_quotes(&app.config().watchlist.ids)
+                .await
+        }
+        Job::Info => app.fetch_token_contracts(&[]).await,
+        Job::Global => app.fetch_global_metrics().await,
+        Job::Retention => app.apply_retention().await.map(|_| ()),
+    }
+}
+
+/// Fetches the latest listings and the trending feeds (if their job is enabled) once.
+async fn fetch_once(app: &App, listing: &ListingArgs) -> Result<(), CmcError> {
+    app.fetch_crypto_data(listing.start, listing.limit, &listing.convert)
+        .await?;
+    if app.config().schedule.trending.enabled {
+        app.fetch_trending().await?;
     }
+
+    Ok(())
+}
+
+/// Fetches the whole map of cryptocurrencies with the given listing statuses (all by default).
+async fn fetch_map(app: &App, listing_status: Vec<ListingStatus>) -> Result<(), CmcError> {
+    let listing_status = if listing_status.is_empty() {
+        vec![
+            ListingStatus::Active,
+            ListingStatus::Inactive,
+            ListingStatus::Untracked,
+        ]
+    } else {
+        listing_status
+    };
+
+    app.fetch_crypto_map(&listing_status).await
+}
+
+/// Imports the dump at `path` and reports the number of imported snapshots.
+async fn import(app: &App, path: &Path, dump: Option<Dump>) -> Result<(), CmcError> {
+    let summary = app.import(path, dump).await?;
+    log::info!(
+        "Imported {} map and {} listing snapshots ({} skipped)",
+        summary.maps,
+        summary.listings,
+        summary.failed
+    );
+
+    Ok(())
+}
+
+/// Prints the usage of the API key.
+async fn credits(app: &App) -> Result<(), CmcError> {
+    let data = app.fetch_key_info().await?;
+    let (plan, usage) = (&data.plan, &data.usage);
+
+    println!(
+        "Requests this minute: {}/{}",
+        usage.current_minute.requests_made, plan.rate_limit_minute
+    );
+    match plan.credit_limit_daily {
+        Some(limit) => println!(
+            "Credits today: {}/{}",
+            usage.current_day.credits_used, limit
+        ),
+        None => println!("Credits today: {}", usage.current_day.credits_used),
+    }
+    println!(
+        "Credits this month: {}/{} ({} left until {})",
+        usage.current_month.credits_used,
+        plan.credit_limit_monthly,
+        data.monthly_credits_left(),
+        plan.credit_limit_monthly_reset_timestamp
+    );
+
+    Ok(())
+}
+
+/// Prints the state of the database schema and, if up to date, a summary of the local cache.
+async fn status(app: &App) -> Result<(), CmcError> {
+    let pending = app.pending_migrations().await?;
+    if !pending.is_empty() {
+        println!("Schema: outdated, pending migrations {:?}", pending);
+        return Ok(());
+    }
+    println!("Schema: up to date");
+
+    let status = app.cache_status().await?;
+    println!("Cryptocurrencies: {}", status.cryptocurrencies);
+    match status.last_listing {
+        Some(last_listing) => {
+            println!("Listings: {} (latest at {})", status.listings, last_listing)
+        }
+        None => println!("Listings: {}", status.listings),
+    }
+    match status.key_usage {
+        Some((fetched_at, credits_left)) => println!(
+            "Credits left this month: {} (as of {})",
+            credits_left, fetched_at
+        ),
+        None => println!("Credits left this month: unknown"),
+    }
+    match app.ingest_runs(1).await?.pop() {
+        Some(run) => match (run.finished_at, &run.error) {
+            (None, _) => println!("Last ingestion: running since {}", run.started_at),
+            (Some(finished_at), None) => println!(
+                "Last ingestion: succeeded at {} ({} rows inserted, {} updated, {} rejected, {} \
+                 quarantined, {} credits used)",
+                finished_at,
+                run.rows_inserted,
+                run.rows_updated,
+                run.rows_rejected,
+                run.rows_quarantined,
+                run.credits_used
+            ),
+            (Some(finished_at), Some(error)) => {
+                println!("Last ingestion: failed at {}: {}", finished_at, error)
+            }
+        },
+        None => println!("Last ingestion: never"),
+    }
+
+    Ok(())
+}
+
+/// Parses a comma-separated list of CoinMarketCap `id`s.
+fn parse_ids(s: &str) -> Result<Ids, CmcError> {
+    s.split(',')
+        .map(|id| {
+            id.trim()
+                .parse()
+                .map_err(|_| CmcError::InvalidArgument(format!("invalid id `{}`", id)))
+        })
+        .collect()
+}
+
+/// Parses either an RFC 3339 timestamp or a date (`YYYY-MM-DD`), taken at midnight UTC.
+fn parse_timestamp(s: &str) -> Result<DateTime<Utc>, CmcError> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(Utc.from_utc_datetime(&date.and_hms(0, 0, 0)));
+    }
+
+    DateTime::parse_from_rfc3339(s)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| CmcError::InvalidArgument(format!("invalid timestamp `{}`", s)))
 }
@@ -0,0 +1,203 @@
+//! Keep the daemon running through the failures of its cycles.
+//!
+//! Each cycle (e.g. fetching the latest listings) is retried or skipped according to the class of
+//! its error (see `coin_market::ErrorClass`):
+//!
+//! - transient network errors, rate limits and database errors are retried with an exponential
+//!   backoff, the rate limits waiting at least for the window of the API to reset;
+//! - unparsable responses skip the cycle, since retrying would fail the same way;
+//! - authentication and other fatal errors stop the daemon right away.
+//!
+//! A cycle whose retries are exhausted is skipped as well, and the daemon exits once the cycles of
+//! any job were skipped `max_consecutive_failures` times in a row (each job keeps its own count, so
+//! the successes of a job don't hide the failures of another). On SIGTERM or SIGINT, the cycle in
+//! flight is finished (so are its database transactions) before the daemon shuts down.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::coin_market::{CmcError, ErrorClass};
+use crate::configuration::PollerConfig;
+
+/// Minimum wait after a rate limit error, since the rate limits of the API reset every minute.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Request to shut down the daemon, shared by all its cycles.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    requested: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Returns a shutdown requested by sending `true` on the returned sender.
+    pub fn new() -> (watch::Sender<bool>, Self) {
+        let (sender, requested) = watch::channel(false);
+        (sender, Self { requested })
+    }
+
+    /// Returns a shutdown requested on SIGTERM or SIGINT.
+    pub fn on_signal() -> Self {
+        let (sender, shutdown) = Self::new();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            log::info!("Shutting down once the current cycle is finished");
+            let _ = sender.send(true);
+        });
+
+        shutdown
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    /// Sleeps for `duration` unless the shutdown is requested in the meantime, and returns whether
+    /// it was.
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        if self.is_requested() {
+            return true;
+        }
+
+        let sleep = tokio::time::sleep(duration);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => return false,
+                changed = self.requested.changed() => match changed {
+                    Ok(()) if self.is_requested() => return true,
+                    Ok(()) => {}
+                    // Without a sender, the shutdown can no longer be requested.
+                    Err(_) => {
+                        sleep.await;
+                        return false;
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen to SIGTERM");
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Runs the cycles of the daemon, keeping track of the consecutive failures of each job.
+pub struct Poller {
+    config: PollerConfig,
+    shutdown: Shutdown,
+    /// Consecutive failures by name of the cycle.
+    failures: HashMap<String, u32>,
+}
+
+impl Poller {
+    pub fn new(config: PollerConfig, shutdown: Shutdown) -> Self {
+        Self {
+            config,
+            shutdown,
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Number of cycles `name` that failed in a row so far.
+    pub fn consecutive_failures(&self, name: &str) -> u32 {
+        self.failures.get(name).copied().unwrap_or(0)
+    }
+
+    /// Runs the cycle `name` until it succeeds, retrying `job` according to the class of its
+    /// errors, and returns whether it succeeded (`false` if skipped or interrupted by a shutdown).
+    /// Fails only if the daemon must exit, i.e. on a fatal error or once too many cycles `name`
+    /// failed in a row.
+    pub async fn cycle<F, Fut>(&mut self, name: &str, mut job: F) -> Result<bool, CmcError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), CmcError>>,
+    {
+        let mut retries = 0;
+
+        loop {
+            let error = match job().await {
+                Ok(()) => {
+                    self.failures.remove(name);
+                    return Ok(true);
+                }
+                Err(error) => error,
+            };
+
+            let class = error.class();
+            let delay = match class {
+                ErrorClass::Auth | ErrorClass::Fatal => {
+                    log::error!("{} failed ({:?}), exiting: {}", name, class, error);
+                    return Err(error);
+                }
+                ErrorClass::Schema => None,
+                ErrorClass::Transient | ErrorClass::Database => self.backoff(retries),
+                ErrorClass::RateLimited => self
+                    .backoff(retries)
+                    .map(|delay| delay.max(RATE_LIMIT_WINDOW)),
+            };
+
+            match delay {
+                Some(delay) => {
+                    retries += 1;
+                    log::warn!(
+                        "{} failed ({:?}), retry {}/{} in {:?}: {}",
+                        name,
+                        class,
+                        retries,
+                        self.config.max_retries,
+                        delay,
+                        error
+                    );
+                    if self.shutdown.sleep(delay).await {
+                        return Ok(false);
+                    }
+                }
+                None => {
+                    let failures = self.failures.entry(name.to_string()).or_default();
+                    *failures += 1;
+                    log::error!(
+                        "{} failed ({:?}), skipping the cycle ({} consecutive failures): {}",
+                        name,
+                        class,
+                        failures,
+                        error
+                    );
+                    if self.config.max_consecutive_failures > 0
+                        && *failures >= self.config.max_consecutive_failures
+                    {
+                        return Err(error);
+                    }
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Delay before the retry following `retries` previous ones, if any retry is left.
+    fn backoff(&self, retries: u32) -> Option<Duration> {
+        if retries >= self.config.max_retries {
+            return None;
+        }
+
+        let delay = self
+            .config
+            .backoff_initial
+            .saturating_mul(2u64.saturating_pow(retries))
+            .min(self.config.backoff_max);
+        Some(Duration::from_secs(delay))
+    }
+}
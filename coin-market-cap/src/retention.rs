@@ -0,0 +1,140 @@
+//! Keep the history of the listings from growing without bounds, since a snapshot of every
+//! cryptocurrency is appended to `crypto_listing` every few minutes.
+//!
+//! `crypto_listing` is partitioned by month (in UTC). The `retention` job creates the partitions
+//! ahead of time, as well as those of the snapshots that landed in the default partition (e.g.
+//! backfilled history). It also rolls the snapshots up per hour and per day (open, high, low and
+//! close prices, average volume and last market cap) into `crypto_listing_hourly` and
+//! `crypto_listing_daily`. Once a partition is entirely older than `raw_max_age` days (see the
+//! `retention` section of the configuration), it is rolled up in full and then dropped.
+
+use chrono::{prelude::*, Duration};
+use sqlx::PgPool;
+
+use crate::configuration::RetentionConfig;
+use crate::database;
+
+/// Granularity of the aggregates of the snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Hourly,
+    Daily,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 2] = [Resolution::Hourly, Resolution::Daily];
+
+    /// Table of the aggregates.
+    pub fn table(&self) -> &'static str {
+        match self {
+            Resolution::Hourly => "crypto_listing_hourly",
+            Resolution::Daily => "crypto_listing_daily",
+        }
+    }
+
+    /// Unit of `date_trunc` truncating a timestamp to its bucket.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Resolution::Hourly => "hour",
+            Resolution::Daily => "day",
+        }
+    }
+}
+
+/// Outcome of a run of the retention.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RetentionSummary {
+    pub partitions_created: u32,
+    /// Hourly and daily aggregates written.
+    pub aggregates: u64,
+    /// Names of the partitions dropped.
+    pub partitions_dropped: Vec<String>,
+}
+
+/// Returns the start of the month (in UTC) of `time`.
+pub fn month_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.ymd(time.year(), time.month(), 1).and_hms(0, 0, 0)
+}
+
+/// Returns the start of the month following `month`.
+fn next_month(month: DateTime<Utc>) -> DateTime<Utc> {
+    match month.month() {
+        12 => Utc.ymd(month.year() + 1, 1, 1).and_hms(0, 0, 0),
+        m => Utc.ymd(month.year(), m + 1, 1).and_hms(0, 0, 0),
+    }
+}
+
+/// Returns the start of the month held by the partition `name`, e.g. `crypto_listing_2022_01`.
+pub fn partition_month(name: &str) -> Option<DateTime<Utc>> {
+    let (year, month) = name.strip_prefix("crypto_listing_")?.split_once('_')?;
+    let date = Utc
+        .ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+        .single()?;
+
+    Some(date.and_hms(0, 0, 0))
+}
+
+/// Creates the missing partitions of `crypto_listing`, rolls up the snapshots of the last
+/// `rollup_lookback` hours and drops the partitions older than `raw_max_age` days, as of `now`.
+pub async fn apply(
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<RetentionSummary, sqlx::Error> {
+    let mut summary = RetentionSummary::default();
+
+    let mut months = database::get_unpartitioned_months(pool.clone()).await?;
+    let mut month = month_start(now);
+    for _ in 0..=config.premake_months {
+        months.push(month);
+        month = next_month(month);
+    }
+    for month in months {
+        if database::create_listing_partition(month, pool.clone()).await? {
+            log::info!("Created the partition of {}", month.format("%Y-%m"));
+            summary.partitions_created += 1;
+        }
+    }
+
+    // From the start of a day, so that no aggregate is replaced by a partial one.
+    let since = (now - Duration::hours(config.rollup_lookback as i64))
+        .date()
+        .and_hms(0, 0, 0);
+    summary.aggregates += rollup(since, now, pool.clone()).await?;
+
+    if let Some(max_age) = config.raw_max_age {
+        let cutoff = now - Duration::days(max_age as i64);
+        for name in database::get_listing_partitions(pool.clone()).await? {
+            let month = match partition_month(&name) {
+                Some(month) => month,
+                None => continue,
+            };
+            // The partitions are sorted from the oldest.
+            if next_month(month) > cutoff {
+                break;
+            }
+
+            summary.aggregates += rollup(month, next_month(month), pool.clone()).await?;
+            database::drop_listing_partition(&name, pool.clone()).await?;
+            log::info!("Dropped the partition {} once rolled up", name);
+            summary.partitions_dropped.push(name);
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Rolls up the snapshots updated during `[since, until)` at every resolution.
+async fn rollup(
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    pool: PgPool,
+) -> Result<u64, sqlx::Error> {
+    let mut aggregates = 0;
+    for resolution in Resolution::ALL {
+        aggregates +=
+            database::rollup_crypto_listings(resolution, since, until, pool.clone()).await?;
+    }
+
+    Ok(aggregates)
+}
@@ -0,0 +1,180 @@
+//! Schedule the jobs of the daemon, since the data fetched by each of them changes at very
+//! different rates (e.g. the map daily, the listings every few minutes).
+//!
+//! Each enabled job of the `schedule` section of the configuration runs either every `interval`
+//! seconds (counted from the end of its previous run) or on a `cron` expression, delayed by up to
+//! `jitter` random seconds. The jobs run one at a time, so that they never compete for the rate
+//! limit of the API: a job due while another one runs starts right after it.
+
+use chrono::{prelude::*, Duration};
+use rand::Rng;
+use std::str::FromStr;
+
+use crate::coin_market::CmcError;
+use crate::configuration::{JobConfig, ScheduleConfig};
+
+/// Jobs of the daemon, in order of priority when several of them are due at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Job {
+    KeyInfo,
+    Map,
+    Listing,
+    Trending,
+    Quotes,
+    Info,
+    Global,
+    Retention,
+}
+
+impl Job {
+    pub const ALL: [Job; 8] = [
+        Job::KeyInfo,
+        Job::Map,
+        Job::Listing,
+        Job::Trending,
+        Job::Quotes,
+        Job::Info,
+        Job::Global,
+        Job::Retention,
+    ];
+
+    /// Name of the job in the `schedule` section of the configuration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Job::KeyInfo => "key_info",
+            Job::Map => "map",
+            Job::Listing => "listing",
+            Job::Trending => "trending",
+            Job::Quotes => "quotes",
+            Job::Info => "info",
+            Job::Global => "global",
+            Job::Retention => "retention",
+        }
+    }
+
+    fn config(self, schedule: &ScheduleConfig) -> &JobConfig {
+        match self {
+            Job::KeyInfo => &schedule.key_info,
+            Job::Map => &schedule.map,
+            Job::Listing => &schedule.listing,
+            Job::Trending => &schedule.trending,
+            Job::Quotes => &schedule.quotes,
+            Job::Info => &schedule.info,
+            Job::Global => &schedule.global,
+            Job::Retention => &schedule.retention,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Timing {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+#[derive(Debug)]
+struct Entry {
+    job: Job,
+    timing: Timing,
+    jitter: u64,
+    next: DateTime<Utc>,
+}
+
+impl Entry {
+    /// Returns the next run after `now`, if any, once delayed by the jitter.
+    fn next_after(&self, now: DateTime<Utc>, first: bool) -> Option<DateTime<Utc>> {
+        let next = match &self.timing {
+            // Jobs with an interval run right away at startup.
+            Timing::Interval(_) if first => now,
+            Timing::Interval(interval) => now + *interval,
+            Timing::Cron(schedule) => schedule.after(&now).next()?,
+        };
+        let jitter = rand::thread_rng().gen_range(0..=self.jitter);
+
+        Some(next + Duration::seconds(jitter as i64))
+    }
+}
+
+/// Next runs of the enabled jobs.
+#[derive(Debug)]
+pub struct Scheduler {
+    entries: Vec<Entry>,
+}
+
+impl Scheduler {
+    /// Schedules the enabled jobs of `schedule` from `now`. Fails if no job is enabled, or if the
+    /// timing of one of them is missing or invalid.
+    pub fn new(schedule: &ScheduleConfig, now: DateTime<Utc>) -> Result<Self, CmcError> {
+        let invalid = |job: Job, message: String| {
+            CmcError::InvalidArgument(format!("job `{}` {}", job.as_str(), message))
+        };
+
+        let mut entries = Vec::new();
+        for job in Job::ALL {
+            let config = job.config(schedule);
+            if !config.enabled {
+                continue;
+            }
+
+            let timing = match (&config.cron, config.interval) {
+                (Some(expression), _) => cron::Schedule::from_str(expression)
+                    .map(|schedule| Timing::Cron(Box::new(schedule)))
+                    .map_err(|error| {
+                        invalid(
+                            job,
+                            format!("has an invalid cron `{}`: {}", expression, error),
+                        )
+                    })?,
+                (None, Some(interval)) if interval > 0 => {
+                    Timing::Interval(Duration::seconds(interval as i64))
+                }
+                (None, _) => {
+                    return Err(invalid(job, "needs a positive interval or a cron".into()))
+                }
+            };
+            let mut entry = Entry {
+                job,
+                timing,
+                jitter: config.jitter,
+                next: now,
+            };
+            entry.next = entry
+                .next_after(now, true)
+                .ok_or_else(|| invalid(job, "never runs".to_string()))?;
+            entries.push(entry);
+        }
+
+        if entries.is_empty() {
+            return Err(CmcError::InvalidArgument(
+                "no job is enabled in the `schedule` section".to_string(),
+            ));
+        }
+        for entry in &entries {
+            log::info!("Job `{}` first runs at {}", entry.job.as_str(), entry.next);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Returns the job due first, along with when it is due (on ties, the first of `Job::ALL`).
+    /// Returns `None` once no job will ever run again.
+    pub fn next(&self) -> Option<(Job, DateTime<Utc>)> {
+        self.entries
+            .iter()
+            .min_by_key(|entry| entry.next)
+            .map(|entry| (entry.job, entry.next))
+    }
+
+    /// Schedules the next run of `job`, which just ran at `now` (whether it succeeded or not).
+    pub fn reschedule(&mut self, job: Job, now: DateTime<Utc>) {
+        if let Some(index) = self.entries.iter().position(|entry| entry.job == job) {
+            match self.entries[index].next_after(now, false) {
+                Some(next) => self.entries[index].next = next,
+                None => {
+                    log::info!("Job `{}` will not run anymore", job.as_str());
+                    self.entries.remove(index);
+                }
+            }
+        }
+    }
+}
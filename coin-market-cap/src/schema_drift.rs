@@ -0,0 +1,247 @@
+//! Module that detects drift between the responses of the CoinMarketCap API and the model of the
+//! `coin_market` module, i.e. unknown fields that are ignored during deserialization and expected
+//! fields that are missing in the response (which silently become `None` if optional).
+//!
+//! The JSON body is deserialized through a `Probe`, which wraps a `serde_json::Value` and compares
+//! the fields of every JSON object with the fields of the struct being deserialized from it. Paths
+//! are dot-separated and array elements (or the values of maps with dynamic keys) are denoted by
+//! `*`, e.g. `data.*.quote.USD.price`.
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+/// Fields of a response that differ from the model.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Drift {
+    /// Fields present in the response but unknown to the model.
+    pub unknown: BTreeSet<String>,
+    /// Fields expected by the model but missing in the response.
+    pub missing: BTreeSet<String>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Deserializes an instance of `T` from `value`, returning it along with the drift between both.
+pub fn deserialize<T: DeserializeOwned>(value: &Value) -> Result<(T, Drift), serde_json::Error> {
+    let drift = RefCell::new(Drift::default());
+    let probe = Probe {
+        value,
+        path: String::new(),
+        drift: &drift,
+    };
+    let data = T::deserialize(probe)?;
+
+    Ok((data, drift.into_inner()))
+}
+
+/// Deserializer of a JSON value at `path` that records the drift of the structs found in it.
+struct Probe<'de> {
+    value: &'de Value,
+    path: String,
+    drift: &'de RefCell<Drift>,
+}
+
+impl<'de> Probe<'de> {
+    fn child(&self, value: &'de Value, field: &str) -> Probe<'de> {
+        let path = if self.path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", self.path, field)
+        };
+
+        Probe {
+            value,
+            path,
+            drift: self.drift,
+        }
+    }
+
+    fn visit_object<V: Visitor<'de>>(
+        self,
+        object: &'de Map<String, Value>,
+        dynamic_keys: bool,
+        visitor: V,
+    ) -> Result<V::Value, serde_json::Error> {
+        visitor.visit_map(MapProbe {
+            iter: object.iter(),
+            value: None,
+            dynamic_keys,
+            probe: self,
+        })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Probe<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Array(array) => visitor.visit_seq(SeqProbe {
+                iter: array.iter(),
+                probe: &self,
+            }),
+            Value::Object(object) => self.visit_object(object, true, visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let object = match self.value {
+            Value::Object(object) => object,
+            _ => return self.deserialize_any(visitor),
+        };
+
+        {
+            let mut drift = self.drift.borrow_mut();
+            for key in object.keys().filter(|key| !fields.contains(&key.as_str())) {
+                drift.unknown.insert(self.child(&Value::Null, key).path);
+            }
+            for field in fields.iter().filter(|field| !object.contains_key(**field)) {
+                drift.missing.insert(self.child(&Value::Null, field).path);
+            }
+        }
+
+        self.visit_object(object, false, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map identifier
+    }
+}
+
+struct SeqProbe<'de, 'p> {
+    iter: std::slice::Iter<'de, Value>,
+    probe: &'p Probe<'de>,
+}
+
+impl<'de, 'p> de::SeqAccess<'de> for SeqProbe<'de, 'p> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(self.probe.child(value, "*")).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapProbe<'de> {
+    iter: serde_json::map::Iter<'de>,
+    value: Option<(&'de String, &'de Value)>,
+    /// Whether the keys are data (e.g. the ids of `HashMap<u32, _>`) rather than field names.
+    dynamic_keys: bool,
+    probe: Probe<'de>,
+}
+
+impl<'de> de::MapAccess<'de> for MapProbe<'de> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(KeyProbe(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        let field = if self.dynamic_keys { "*" } else { key };
+
+        seed.deserialize(self.probe.child(value, field))
+    }
+}
+
+/// Deserializer of the key of a JSON object, which may also be an integer (e.g. the ids of
+/// `HashMap<u32, _>`).
+struct KeyProbe<'de>(&'de str);
+
+impl<'de> de::Deserializer<'de> for KeyProbe<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.parse() {
+            Ok(key) => visitor.visit_u64(key),
+            Err(_) => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.parse() {
+            Ok(key) => visitor.visit_i64(key),
+            Err(_) => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_u64(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_i64(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i128 u8 u16 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
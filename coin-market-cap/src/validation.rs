@@ -0,0 +1,180 @@
+//! Validate the listings before they are stored, since the API occasionally returns bad data, e.g.
+//! zero prices, negative supplies or absurd jumps of price between two consecutive snapshots.
+//!
+//! Every rule of the `validation` section of the configuration can be disabled. Depending on its
+//! `action`, the records that break any of them are either quarantined in the table
+//! `crypto_listing_quarantine` along with the reasons, or rejected (i.e. only logged and counted).
+//! Either way, they are left out of `crypto_listing`.
+
+use bigdecimal::{BigDecimal, FromPrimitive, Signed};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::coin_market::listing;
+use crate::configuration::ValidationConfig;
+
+/// Fate of the records that fail the validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationAction {
+    /// No validation: every record is stored.
+    Off,
+    /// The records are dropped.
+    Reject,
+    /// The records are stored in `crypto_listing_quarantine` instead.
+    #[default]
+    Quarantine,
+}
+
+/// Rule broken by a record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// The price is zero (or negative).
+    ZeroPrice,
+    /// The given supply is negative.
+    NegativeSupply(&'static str),
+    /// The circulating supply exceeds the maximum supply.
+    SupplyAboveMax,
+    /// The price changed by this percentage since the previous snapshot, relative to the lower of
+    /// both prices (so that a fall to a hundredth is as much of a jump as a rise to a hundredfold).
+    PriceJump(BigDecimal),
+}
+
+impl Violation {
+    /// Name of the rule in the `validation` section of the configuration.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Violation::ZeroPrice => "zero_price",
+            Violation::NegativeSupply(_) => "negative_supply",
+            Violation::SupplyAboveMax => "supply_above_max",
+            Violation::PriceJump(_) => "max_price_change",
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::ZeroPrice => write!(f, "zero price"),
+            Violation::NegativeSupply(field) => write!(f, "negative {}", field),
+            Violation::SupplyAboveMax => write!(f, "circulating_supply above max_supply"),
+            Violation::PriceJump(change) => write!(
+                f,
+                "price changed by {}% since the previous snapshot",
+                change.with_scale(0)
+            ),
+        }
+    }
+}
+
+/// Record that failed the validation.
+#[derive(Debug)]
+pub struct Invalid {
+    pub data: listing::Data,
+    /// Price of the previous snapshot, if any.
+    pub previous_price: Option<BigDecimal>,
+    pub violations: Vec<Violation>,
+}
+
+impl Invalid {
+    pub fn reasons(&self) -> Vec<String> {
+        self.violations.iter().map(ToString::to_string).collect()
+    }
+}
+
+/// Outcome of the validation of a batch of records.
+#[derive(Debug, Default)]
+pub struct Validated {
+    pub valid: Vec<listing::Data>,
+    pub invalid: Vec<Invalid>,
+}
+
+impl Validated {
+    /// Returns the number of violations of each rule.
+    pub fn violations(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for violation in self.invalid.iter().flat_map(|invalid| &invalid.violations) {
+            *counts.entry(violation.rule()).or_default() += 1;
+        }
+
+        counts
+    }
+}
+
+/// Returns the rules of `config` broken by `data`, given the price of the previous snapshot.
+pub fn check(
+    data: &listing::Data,
+    previous_price: Option<&BigDecimal>,
+    config: &ValidationConfig,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let price = data.quote.usd.price.as_ref();
+
+    if config.zero_price && matches!(price, Some(price) if !price.is_positive()) {
+        violations.push(Violation::ZeroPrice);
+    }
+    if config.negative_supply {
+        let supplies = [
+            ("circulating_supply", &data.circulating_supply),
+            ("total_supply", &data.total_supply),
+            ("max_supply", &data.max_supply),
+        ];
+        for (field, supply) in supplies {
+            if matches!(supply, Some(supply) if supply.is_negative()) {
+                violations.push(Violation::NegativeSupply(field));
+            }
+        }
+    }
+    if config.supply_above_max {
+        if let (Some(circulating), Some(max)) = (&data.circulating_supply, &data.max_supply) {
+            if circulating > max {
+                violations.push(Violation::SupplyAboveMax);
+            }
+        }
+    }
+    if let (Some(max_change), Some(price), Some(previous)) = (
+        config.max_price_change.and_then(BigDecimal::from_f64),
+        price,
+        previous_price,
+    ) {
+        if previous.is_positive() && price.is_positive() {
+            let ratio = if price > previous {
+                price / previous
+            } else {
+                previous / price
+            };
+            let change = (ratio - BigDecimal::from(1)) * BigDecimal::from(100);
+            if change > max_change {
+                violations.push(Violation::PriceJump(change));
+            }
+        }
+    }
+
+    violations
+}
+
+/// Splits `data` into the valid and invalid records, given the prices of the previous snapshots by
+/// `id`.
+pub fn validate(
+    data: Vec<listing::Data>,
+    previous_prices: &HashMap<u32, BigDecimal>,
+    config: &ValidationConfig,
+) -> Validated {
+    let mut validated = Validated::default();
+    for data in data {
+        let previous_price = previous_prices.get(&data.id);
+        let violations = check(&data, previous_price, config);
+        if violations.is_empty() {
+            validated.valid.push(data);
+        } else {
+            validated.invalid.push(Invalid {
+                data,
+                previous_price: previous_price.cloned(),
+                violations,
+            });
+        }
+    }
+
+    validated
+}
@@ -0,0 +1,108 @@
+use chrono::Duration;
+use coin_market_cap::coin_market::listing::{CryptocurrencyType, ListingQuery, Sort, SortDir};
+use coin_market_cap::coin_market::{interval_duration, CmcError};
+use rust_decimal::Decimal;
+
+#[test]
+fn listing_query_params() {
+    let query = ListingQuery::new()
+        .start(1)
+        .limit(100)
+        .sort("volume_24h".parse().expect("Failed to parse the sort!"))
+        .sort_dir(SortDir::Desc)
+        .cryptocurrency_type(CryptocurrencyType::Tokens)
+        .volume_24h_min(Decimal::new(1_000_000, 0))
+        .aux(&["cmc_rank", "tags"]);
+
+    let params = query.params().expect("Invalid query!");
+    let expected = [
+        ("start", "1"),
+        ("limit", "100"),
+        ("sort", "volume_24h"),
+        ("sort_dir", "desc"),
+        ("cryptocurrency_type", "tokens"),
+        ("volume_24h_min", "1000000"),
+        ("aux", "cmc_rank,tags"),
+    ];
+    assert_eq!(
+        params,
+        expected
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn listing_query_default_is_empty() {
+    let params = ListingQuery::new().params().expect("Invalid query!");
+    assert!(params.is_empty(), "Unset parameters must not be sent");
+}
+
+#[test]
+fn listing_query_invalid_limit() {
+    assert!(ListingQuery::new().limit(0).validate().is_err());
+    assert!(ListingQuery::new().limit(5001).validate().is_err());
+    assert!(ListingQuery::new().limit(5000).validate().is_ok());
+}
+
+#[test]
+fn listing_query_invalid_ranges() {
+    let (one, two) = (Some(Decimal::ONE), Some(Decimal::TWO));
+
+    assert!(ListingQuery::new().price(two, one).validate().is_err());
+    assert!(ListingQuery::new().market_cap(two, one).validate().is_err());
+    assert!(ListingQuery::new().price(one, two).validate().is_ok());
+    assert!(ListingQuery::new()
+        .volume_24h_min(Decimal::NEGATIVE_ONE)
+        .validate()
+        .is_err());
+    assert!(ListingQuery::new()
+        .percent_change_24h_min(Decimal::new(-101, 0))
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn listing_query_invalid_aux() {
+    assert!(ListingQuery::new()
+        .aux(&["cmc_rank", "unknown"])
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn listing_query_parse_enums() {
+    assert_eq!("market_cap".parse::<Sort>().ok(), Some(Sort::MarketCap));
+    assert!("market-cap".parse::<Sort>().is_err());
+    assert!("up".parse::<SortDir>().is_err());
+    assert_eq!(
+        "coins".parse::<CryptocurrencyType>().ok(),
+        Some(CryptocurrencyType::Coins)
+    );
+}
+
+#[test]
+fn historical_interval_duration() {
+    assert_eq!(interval_duration("5m").unwrap(), Duration::minutes(5));
+    assert_eq!(interval_duration("12h").unwrap(), Duration::hours(12));
+    assert_eq!(interval_duration("daily").unwrap(), Duration::days(1));
+
+    for invalid in [
+        "",
+        "m",
+        "0d",
+        "-1h",
+        "5s",
+        "5µ",
+        "é",
+        "99999999999999999m",
+        "9223372036854775807d",
+        "99999999999999999999h",
+    ] {
+        assert!(matches!(
+            interval_duration(invalid),
+            Err(CmcError::InvalidArgument(_))
+        ));
+    }
+}
@@ -1,4 +1,5 @@
-use coin_market_cap::configuration;
+use coin_market_cap::configuration::{self, ListingConfig};
+use rust_decimal::Decimal;
 
 /// Loads the configuration and ensures that your API key is set.
 #[tokio::test]
@@ -58,3 +59,31 @@ async fn config_check_coin_market() {
         "The CoinMarketCap `api_key` is empty!"
     );
 }
+
+/// Parses the filters of the listings, with the monetary bounds as decimals.
+#[test]
+fn config_parse_listing_filters() {
+    let yaml = r#"
+price_min: "0.00000001"
+price_max: 1.5
+market_cap_min: 1000000
+market_cap_max: "123456789012.34"
+volume_24h_min: 50000
+percent_change_24h_min: -5
+"#;
+    let mut settings = config::Config::default();
+    settings
+        .merge(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .expect("Error parsing the filters!");
+    let listing: ListingConfig = settings.try_into().expect("Invalid filters!");
+
+    assert_eq!(listing.price_min, Some(Decimal::new(1, 8)));
+    assert_eq!(listing.price_max, Some(Decimal::new(15, 1)));
+    assert_eq!(listing.market_cap_min, Some(Decimal::new(1_000_000, 0)));
+    assert_eq!(
+        listing.market_cap_max,
+        Some(Decimal::new(12_345_678_901_234, 2))
+    );
+    assert_eq!(listing.volume_24h_min, Some(Decimal::new(50_000, 0)));
+    assert_eq!(listing.percent_change_24h_min, Some(Decimal::new(-5, 0)));
+}
@@ -0,0 +1,60 @@
+use coin_market_cap::configuration::{self, LeaderConfig};
+use coin_market_cap::leader::Leadership;
+use coin_market_cap::scheduler::Job;
+
+/// Two replicas of the daemon, with their own connection to the database.
+fn replicas(lock_namespace: i32) -> (Leadership, Leadership) {
+    let config = configuration::load_config().expect("Error loading the configuration!");
+    let leader = LeaderConfig {
+        enabled: true,
+        lock_namespace,
+    };
+
+    (
+        Leadership::new(&leader, &config.database),
+        Leadership::new(&leader, &config.database),
+    )
+}
+
+/// Each job is led by the first replica to claim it, until it resigns.
+#[tokio::test]
+async fn elect_leader_per_job() -> Result<(), sqlx::Error> {
+    let (mut first, mut second) = replicas(-47_001);
+
+    assert!(first.lead(Job::Listing).await?);
+    assert!(!second.lead(Job::Listing).await?);
+    assert!(second.lead(Job::Map).await?);
+    assert!(!first.lead(Job::Map).await?);
+    // The lead is kept between runs.
+    assert!(first.lead(Job::Listing).await?);
+    assert!(!second.lead(Job::Listing).await?);
+
+    first.resign().await;
+    assert!(second.lead(Job::Listing).await?);
+    second.resign().await;
+
+    Ok(())
+}
+
+/// The locks of a replica that dies (i.e. whose session ends) are released.
+#[tokio::test]
+async fn take_over_dead_leader() -> Result<(), sqlx::Error> {
+    let (mut first, mut second) = replicas(-47_002);
+
+    assert!(first.lead(Job::Global).await?);
+    assert!(!second.lead(Job::Global).await?);
+    drop(first);
+    // The server notices the end of the session shortly after the connection is dropped.
+    let mut took_over = false;
+    for _ in 0..50 {
+        if second.lead(Job::Global).await? {
+            took_over = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(took_over);
+    second.resign().await;
+
+    Ok(())
+}
@@ -1,11 +1,12 @@
-use coin_market_cap::coin_market;
+use coin_market_cap::coin_market::{self, App};
 
 /// Be aware that this test **makes a real request** to the endpoint of the CoinMarketCap API.
 #[tokio::test]
 #[ignore]
 async fn fetch_crypto_listing() {
+    let app = App::new();
     // Pull new data from the server
-    match coin_market::listing::request_crypto_listing(1, 100, "USD").await {
+    match coin_market::listing::request_crypto_listing(&app.client(), 1, 100, "USD").await {
         Ok(response) => {
             assert!(
                 response.data.len() == 100,
@@ -20,8 +21,9 @@ async fn fetch_crypto_listing() {
 #[tokio::test]
 #[ignore]
 async fn fetch_crypto_map() {
+    let app = App::new();
     // Pull new data from the server
-    match coin_market::map::request_crypto_map(1, 100, "cmc_rank").await {
+    match coin_market::map::request_crypto_map(&app.client(), 1, 100, "cmc_rank").await {
         Ok(response) => {
             assert!(
                 response.data.len() == 100,
@@ -31,3 +33,30 @@ async fn fetch_crypto_map() {
         Err(error) => panic!("Error calling `request_crypto_map`: {}", error),
     }
 }
+
+/// Be aware that this test **makes a real request** to the endpoint of the CoinMarketCap API.
+#[tokio::test]
+#[ignore]
+async fn fetch_crypto_quotes_historical() {
+    let time_start = chrono::Utc::now() - chrono::Duration::days(30);
+    let app = App::new();
+    // Pull new data from the server
+    match coin_market::quotes_historical::request_quotes_historical(
+        &app.client(),
+        1,
+        time_start,
+        10,
+        "daily",
+        "USD",
+    )
+    .await
+    {
+        Ok(response) => {
+            assert!(
+                response.data[&1].quotes.len() == 10,
+                "Error parsing the response from the server"
+            );
+        }
+        Err(error) => panic!("Error calling `request_quotes_historical`: {}", error),
+    }
+}
@@ -1,5 +1,14 @@
-use coin_market_cap::coin_market::{listing, map};
+use bigdecimal::BigDecimal;
+use coin_market_cap::{
+    coin_market::{
+        categories, category, global_metrics, info, key_info, listing, map, ohlcv_historical,
+        quotes_historical, quotes_latest, trending,
+    },
+    import::Dump,
+    schema_drift,
+};
 use std::collections::BTreeSet;
+use std::str::FromStr;
 
 #[test]
 fn parse_crypto_listing_1() {
@@ -74,6 +83,127 @@ fn parse_crypto_listing_ignored_fields_fails() {
     assert!(unused.is_empty(), "The json file contains ignored fields!");
 }
 
+#[test]
+fn parse_crypto_listing_nulls_3() {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_nulls_3.json");
+    // Read the JSON contents of the string as an instance of `listing::Response`.
+    let response: listing::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert!(
+        response.data.len() == 3,
+        "Error parsing `cryptocurrency_listings_latest_nulls_3.json`"
+    );
+
+    // Null fields.
+    let (data, changes) = (&response.data[0], &response.data[0].quote.usd);
+    assert!(data.circulating_supply.is_none() && data.total_supply.is_none());
+    assert_eq!(
+        changes.missing_fields(),
+        [
+            "volume_change_24h",
+            "market_cap_dominance",
+            "fully_diluted_market_cap"
+        ]
+    );
+
+    // Missing fields, exponent notation and numeric strings.
+    let (data, changes) = (&response.data[1], &response.data[1].quote.usd);
+    assert!(data.total_supply.is_none());
+    assert_eq!(
+        changes.missing_fields(),
+        ["volume_change_24h", "percent_change_90d"]
+    );
+    assert_eq!(changes.market_cap, "497866100000".parse().ok());
+    assert!(changes.fully_diluted_market_cap.is_some());
+
+    // Null price.
+    assert!(response.data[2].quote.usd.price.is_none());
+}
+
+#[test]
+fn parse_crypto_listing_overflow_1() {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_overflow_1.json");
+    // Read the JSON contents of the string as an instance of `listing::Response`.
+    let response: listing::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    // Values beyond the range of `Decimal` are kept without any loss of precision.
+    let data = &response.data[0];
+    let max_supply: BigDecimal = "1e39".parse().unwrap();
+    assert_eq!(data.max_supply, Some(max_supply));
+    assert_eq!(
+        data.total_supply
+            .as_ref()
+            .map(ToString::to_string)
+            .as_deref(),
+        Some("999999999999999999999999999999999999.123456789")
+    );
+    assert!(data.circulating_supply.is_some());
+    assert!(data.quote.usd.fully_diluted_market_cap.is_some());
+}
+
+#[test]
+fn parse_crypto_listing_malformed_4() {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_malformed_4.json");
+    let value: serde_json::Value = serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    // ETH has a `null` quote and BNB a quote without `last_updated`, the other listings are kept.
+    let response: listing::Response =
+        serde_json::from_value(value.clone()).expect("Failed to parse input!");
+    let symbols: Vec<&str> = response
+        .data
+        .iter()
+        .map(|data| data.symbol.as_str())
+        .collect();
+    assert_eq!(symbols, ["BTC", "ADA"]);
+    assert_eq!(response.skipped, 2);
+
+    // Likewise along with the detection of the schema drift.
+    let (response, _): (listing::Response, _) =
+        schema_drift::deserialize(&value).expect("Failed to parse input!");
+    assert_eq!(response.data.len(), 2);
+    assert_eq!(response.skipped, 2);
+}
+
+#[test]
+fn detect_schema_drift_listing() {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_ignored_fields.json");
+    let value: serde_json::Value = serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let (response, drift): (listing::Response, _) =
+        schema_drift::deserialize(&value).expect("Failed to parse input!");
+
+    assert!(
+        response.data.len() == 2,
+        "Error parsing `cryptocurrency_listing_ignored_fields.json`"
+    );
+
+    // The same keys ignored by `serde_ignored`, regardless of the element of `data`.
+    let mut expected = BTreeSet::new();
+    expected.insert("status.ignored_field_1".to_string());
+    expected.insert("data.*.ignored_field_2".to_string());
+    expected.insert("data.*.quote.USD.ignored_field_3".to_string());
+    assert_eq!(drift.unknown, expected);
+    assert!(drift.missing.is_empty());
+}
+
+#[test]
+fn detect_schema_drift_missing_fields() {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_nulls_3.json");
+    let value: serde_json::Value = serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let (_, drift): (listing::Response, _) =
+        schema_drift::deserialize(&value).expect("Failed to parse input!");
+
+    let mut expected = BTreeSet::new();
+    expected.insert("data.*.total_supply".to_string());
+    expected.insert("data.*.quote.USD.volume_change_24h".to_string());
+    expected.insert("data.*.quote.USD.percent_change_90d".to_string());
+    assert!(drift.unknown.is_empty());
+    assert_eq!(drift.missing, expected);
+}
+
 #[test]
 fn parse_crypto_map_50() {
     let str_json = include_str!("data/cryptocurrency_map_50.json");
@@ -86,6 +216,24 @@ fn parse_crypto_map_50() {
     );
 }
 
+#[test]
+fn parse_crypto_map_inactive_untracked_3() {
+    let str_json = include_str!("data/cryptocurrency_map_inactive_untracked_3.json");
+    // Read the JSON contents of the string as an instance of `map::Response`.
+    let response: map::Response = serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert!(
+        response.data.len() == 3,
+        "Error parsing `cryptocurrency_map_inactive_untracked_3.json`"
+    );
+    assert!(response
+        .data
+        .iter()
+        .all(|data| data.is_active == Some(false)));
+    assert_eq!(response.data[2].status.as_deref(), Some("untracked"));
+    assert!(response.data[2].first_historical_data.is_none());
+}
+
 #[test]
 fn parse_crypto_map_0() {
     let str_json = include_str!("data/cryptocurrency_map_0.json");
@@ -97,3 +245,170 @@ fn parse_crypto_map_0() {
         "Error parsing empty response data"
     );
 }
+
+#[test]
+fn parse_crypto_quotes_historical_3() {
+    let str_json = include_str!("data/cryptocurrency_quotes_historical_3.json");
+    // Read the JSON contents of the string as an instance of `quotes_historical::Response`.
+    let response: quotes_historical::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let data = response.data.get(&1).expect("Missing data of id 1");
+    assert!(
+        data.quotes.len() == 3,
+        "Error parsing `cryptocurrency_quotes_historical_3.json`"
+    );
+    assert_eq!(response.status.credit_count, 1);
+}
+
+#[test]
+fn parse_crypto_ohlcv_historical_3() {
+    let str_json = include_str!("data/cryptocurrency_ohlcv_historical_3.json");
+    // Read the JSON contents of the string as an instance of `ohlcv_historical::Response`.
+    let response: ohlcv_historical::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let data = response.data.get(&1).expect("Missing data of id 1");
+    assert!(
+        data.quotes.len() == 3,
+        "Error parsing `cryptocurrency_ohlcv_historical_3.json`"
+    );
+    assert!(data
+        .quotes
+        .iter()
+        .all(|quote| quote.time_open < quote.time_close));
+}
+
+#[test]
+fn parse_crypto_categories_3() {
+    let str_json = include_str!("data/cryptocurrency_categories_3.json");
+    // Read the JSON contents of the string as an instance of `categories::Response`.
+    let response: categories::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert!(
+        response.data.len() == 3,
+        "Error parsing `cryptocurrency_categories_3.json`"
+    );
+}
+
+#[test]
+fn parse_crypto_category_4() {
+    let str_json = include_str!("data/cryptocurrency_category_4.json");
+    // Read the JSON contents of the string as an instance of `category::Response`.
+    let response: category::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert!(
+        response.data.coins.len() == 4,
+        "Error parsing `cryptocurrency_category_4.json`"
+    );
+}
+
+#[test]
+fn parse_crypto_trending_gainers_losers_5() {
+    let str_json = include_str!("data/cryptocurrency_trending_gainers_losers_5.json");
+    // Read the JSON contents of the string as an instance of `trending::Response`.
+    let response: trending::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert!(
+        response.data.len() == 5,
+        "Error parsing `cryptocurrency_trending_gainers_losers_5.json`"
+    );
+
+    // The gainers are sorted by their percent change in descending order.
+    let changes: Vec<_> = response
+        .data
+        .iter()
+        .map(|data| trending::percent_change(&data.quote.usd, "24h").unwrap())
+        .collect();
+    assert!(changes.windows(2).all(|pair| pair[0] >= pair[1]));
+}
+
+#[test]
+fn parse_crypto_info_3() {
+    let str_json = include_str!("data/cryptocurrency_info_3.json");
+    // Read the JSON contents of the string as an instance of `info::Response`.
+    let response: info::Response = serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert_eq!(response.data.len(), 3);
+    assert!(response.data[&1].contract_address.is_empty());
+
+    let tether = &response.data[&825];
+    assert_eq!(
+        tether.platform.as_ref().map(|platform| platform.id),
+        Some(1027)
+    );
+    assert_eq!(tether.contract_address.len(), 4);
+    let bep20 = &tether.contract_address[2];
+    assert_eq!(bep20.platform.name, "Binance Smart Chain (BEP20)");
+    assert_eq!(bep20.platform.coin.id, 1839);
+}
+
+/// The tags of the v2 quotes are objects, stored by their slug like those of the listings.
+#[test]
+fn parse_crypto_quotes_latest_2() {
+    let str_json = include_str!("data/cryptocurrency_quotes_latest_2.json");
+    // Read the JSON contents of the string as an instance of `quotes_latest::Response`.
+    let response: quotes_latest::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert_eq!(response.data.len(), 2);
+    let bitcoin = &response.data[&1];
+    assert_eq!(bitcoin.symbol, "BTC");
+    assert_eq!(
+        bitcoin.tags.as_deref(),
+        Some(
+            &[
+                "mineable".to_string(),
+                "pow".to_string(),
+                "sha-256".to_string()
+            ][..]
+        )
+    );
+    assert!(bitcoin.quote.usd.price.is_some());
+}
+
+#[test]
+fn parse_global_metrics() {
+    let str_json = include_str!("data/global_metrics_quotes_latest.json");
+    // Read the JSON contents of the string as an instance of `global_metrics::Response`.
+    let response: global_metrics::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert_eq!(response.data.active_cryptocurrencies, 6723);
+    assert_eq!(
+        response.data.btc_dominance,
+        Some(BigDecimal::from_str("45.096329").unwrap())
+    );
+    assert!(response.data.quote.usd.total_market_cap.is_some());
+}
+
+#[test]
+fn parse_key_info() {
+    let str_json = include_str!("data/key_info.json");
+    // Read the JSON contents of the string as an instance of `key_info::Response`.
+    let response: key_info::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    assert_eq!(response.data.plan.credit_limit_monthly, 120000);
+    assert_eq!(response.data.usage.current_minute.requests_left, 59);
+    assert_eq!(response.data.monthly_credits_left(), 119968);
+}
+
+#[test]
+fn detect_dump() {
+    let detect = |str_json: &str| Dump::detect(&serde_json::from_str(str_json).unwrap());
+
+    assert_eq!(
+        detect(include_str!("data/cryptocurrency_map_50.json")),
+        Some(Dump::Map)
+    );
+    assert_eq!(
+        detect(include_str!("data/cryptocurrency_listings_latest_4.json")),
+        Some(Dump::Listing)
+    );
+    assert_eq!(detect(include_str!("data/cryptocurrency_map_0.json")), None);
+    assert_eq!(detect(include_str!("data/key_info.json")), None);
+}
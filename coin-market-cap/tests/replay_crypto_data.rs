@@ -0,0 +1,125 @@
+use chrono::prelude::*;
+use coin_market_cap::coin_market::{self, App, CmcError};
+use coin_market_cap::{configuration, database, import::Dump};
+
+/// Replays the cassettes in `tests/cassettes` instead of requesting the CoinMarketCap API (see
+/// `config/test.yaml`), so these tests run offline and without any API key.
+fn replay() {
+    std::env::set_var("APP_ENVIRONMENT", "test");
+}
+
+#[tokio::test]
+async fn replay_crypto_listing() -> Result<(), CmcError> {
+    replay();
+    let app = App::new();
+    let response =
+        coin_market::listing::request_crypto_listing(&app.client(), 1, 100, "USD").await?;
+
+    assert_eq!(response.data.len(), 100);
+    assert_eq!(response.data[0].symbol, "BTC");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_crypto_map() -> Result<(), CmcError> {
+    replay();
+    let app = App::new();
+    let response = coin_market::map::request_crypto_map(&app.client(), 1, 100, "cmc_rank").await?;
+
+    assert_eq!(response.data.len(), 100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_key_info() -> Result<(), CmcError> {
+    replay();
+    let app = App::new();
+    let response = coin_market::key_info::request_key_info(&app.client()).await?;
+
+    assert_eq!(response.data.plan.credit_limit_monthly, 120000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_crypto_info() -> Result<(), CmcError> {
+    replay();
+    let app = App::new();
+    let response = coin_market::info::request_crypto_info(&app.client(), &[1, 825, 4687]).await?;
+
+    assert_eq!(response.data.len(), 3);
+
+    Ok(())
+}
+
+/// Errors of the server are recorded as well.
+#[tokio::test]
+async fn replay_api_error() {
+    replay();
+    let time_start = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+    let app = App::new();
+    let response = coin_market::quotes_historical::request_quotes_historical(
+        &app.client(),
+        1,
+        time_start,
+        10,
+        "daily",
+        "USD",
+    )
+    .await;
+
+    assert!(matches!(
+        response,
+        Err(CmcError::Api {
+            error_code: 1006,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn replay_missing_cassette() {
+    replay();
+    let app = App::new();
+    let response =
+        coin_market::listing::request_crypto_listing(&app.client(), 1, 5000, "USD").await;
+
+    assert!(matches!(response, Err(CmcError::MissingCassette(_))));
+}
+
+/// Every fetch of the listings is recorded in `ingest_run`, whether it succeeds or fails.
+#[tokio::test]
+async fn replay_fetch_crypto_data_db() -> Result<(), CmcError> {
+    replay();
+    let app = App::new();
+    let config = configuration::load_config()?;
+    database::clear_all_tables(database::get_connection_pool(&config.database)).await?;
+    // The map is imported, since the cryptocurrencies missing in it would be requested.
+    let data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    app.import(
+        &data.join("cryptocurrency_map_100_ranked.json"),
+        Some(Dump::Map),
+    )
+    .await?;
+
+    app.fetch_crypto_data(1, 100, "USD").await?;
+    let run = app.ingest_runs(1).await?.remove(0);
+    assert_eq!(run.endpoint, "/v1/cryptocurrency/listings/latest");
+    assert_eq!(run.params, "start=1&limit=100&convert=USD");
+    assert!(run.finished_at.is_some());
+    // The circulating supply of renBTC exceeds its max supply in the recorded listings.
+    assert_eq!((run.rows_inserted, run.rows_updated), (99, 0));
+    assert_eq!((run.rows_rejected, run.rows_quarantined), (0, 1));
+    assert_eq!(run.credits_used, 1);
+    assert_eq!(run.error, None);
+
+    assert!(app.fetch_crypto_data(1, 5000, "USD").await.is_err());
+    let run = app.ingest_runs(1).await?.remove(0);
+    assert!(run.finished_at.is_some());
+    assert_eq!(run.rows_inserted, 0);
+    assert!(run.error.unwrap().starts_with("No cassette recorded"));
+
+    Ok(())
+}
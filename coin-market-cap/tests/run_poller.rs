@@ -0,0 +1,146 @@
+use coin_market_cap::coin_market::{CmcError, ErrorClass};
+use coin_market_cap::configuration::PollerConfig;
+use coin_market_cap::poller::{Poller, Shutdown};
+use std::cell::Cell;
+use std::time::Duration;
+
+/// Retries without any delay, so these tests don't wait.
+fn poller(max_consecutive_failures: u32) -> Poller {
+    let config = PollerConfig {
+        max_consecutive_failures,
+        max_retries: 2,
+        backoff_initial: 0,
+        backoff_max: 0,
+    };
+    let (_, shutdown) = Shutdown::new();
+
+    Poller::new(config, shutdown)
+}
+
+fn api_error(error_code: u32) -> CmcError {
+    CmcError::Api {
+        error_code,
+        error_message: String::new(),
+    }
+}
+
+#[test]
+fn classify_errors() {
+    assert_eq!(api_error(1001).class(), ErrorClass::Auth);
+    assert_eq!(api_error(1008).class(), ErrorClass::RateLimited);
+    assert_eq!(api_error(429).class(), ErrorClass::RateLimited);
+    assert_eq!(api_error(503).class(), ErrorClass::Transient);
+    assert_eq!(api_error(400).class(), ErrorClass::Fatal);
+    let parse = serde_json::from_str::<u32>("{").unwrap_err();
+    assert_eq!(CmcError::from(parse).class(), ErrorClass::Schema);
+    assert_eq!(
+        CmcError::DbQuery(sqlx::Error::PoolTimedOut).class(),
+        ErrorClass::Database
+    );
+    assert_eq!(CmcError::OutdatedSchema(vec![1]).class(), ErrorClass::Fatal);
+}
+
+/// Transient errors are retried within the cycle.
+#[tokio::test]
+async fn retry_transient_errors() -> Result<(), CmcError> {
+    let mut poller = poller(5);
+    let attempts = Cell::new(0);
+
+    let succeeded = poller
+        .cycle("test", || async {
+            attempts.set(attempts.get() + 1);
+            match attempts.get() {
+                1 => Err(api_error(500)),
+                2 => Err(CmcError::DbQuery(sqlx::Error::PoolTimedOut)),
+                _ => Ok(()),
+            }
+        })
+        .await?;
+
+    assert!(succeeded);
+    assert_eq!(attempts.get(), 3);
+    assert_eq!(poller.consecutive_failures("test"), 0);
+
+    Ok(())
+}
+
+/// Cycles whose retries are exhausted, or that cannot be parsed, are skipped until too many of
+/// them failed in a row.
+#[tokio::test]
+async fn skip_failed_cycles() {
+    let mut poller = poller(2);
+    let attempts = Cell::new(0);
+
+    let skipped = poller
+        .cycle("test", || async {
+            attempts.set(attempts.get() + 1);
+            Err(api_error(502))
+        })
+        .await;
+    assert!(matches!(skipped, Ok(false)));
+    assert_eq!(attempts.get(), 3);
+    assert_eq!(poller.consecutive_failures("test"), 1);
+
+    let exhausted = poller
+        .cycle("test", || async {
+            Err(serde_json::from_str::<u32>("{").unwrap_err().into())
+        })
+        .await;
+    assert!(matches!(exhausted, Err(CmcError::Parse(_))));
+    assert_eq!(poller.consecutive_failures("test"), 2);
+}
+
+/// The failures of a job are counted apart from the other jobs, whose successes don't reset them.
+#[tokio::test]
+async fn count_failures_per_job() {
+    let mut poller = poller(2);
+
+    let skipped = poller
+        .cycle("listing", || async { Err(api_error(502)) })
+        .await;
+    assert!(matches!(skipped, Ok(false)));
+    let succeeded = poller.cycle("trending", || async { Ok(()) }).await;
+    assert!(matches!(succeeded, Ok(true)));
+    assert_eq!(poller.consecutive_failures("listing"), 1);
+    assert_eq!(poller.consecutive_failures("trending"), 0);
+
+    let exhausted = poller
+        .cycle("listing", || async { Err(api_error(502)) })
+        .await;
+    assert!(matches!(exhausted, Err(CmcError::Api { .. })));
+    assert_eq!(poller.consecutive_failures("listing"), 2);
+}
+
+/// Authentication errors stop the daemon without any retry.
+#[tokio::test]
+async fn exit_on_auth_error() {
+    let mut poller = poller(0);
+    let attempts = Cell::new(0);
+
+    let result = poller
+        .cycle("test", || async {
+            attempts.set(attempts.get() + 1);
+            Err(api_error(1002))
+        })
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(CmcError::Api {
+            error_code: 1002,
+            ..
+        })
+    ));
+    assert_eq!(attempts.get(), 1);
+}
+
+/// A requested shutdown interrupts the sleep between two cycles.
+#[tokio::test]
+async fn interrupt_sleep_on_shutdown() {
+    let (sender, mut shutdown) = Shutdown::new();
+    assert!(!shutdown.sleep(Duration::from_millis(1)).await);
+
+    sender.send(true).unwrap();
+    assert!(shutdown.is_requested());
+    assert!(shutdown.sleep(Duration::from_secs(3600)).await);
+}
@@ -0,0 +1,115 @@
+use chrono::{prelude::*, Duration};
+use coin_market_cap::coin_market::CmcError;
+use coin_market_cap::configuration::{JobConfig, ScheduleConfig};
+use coin_market_cap::scheduler::{Job, Scheduler};
+
+fn now() -> DateTime<Utc> {
+    Utc.ymd(2022, 1, 10).and_hms(12, 0, 0)
+}
+
+/// Returns a schedule with the `job` only.
+fn only(job: Job, config: JobConfig) -> ScheduleConfig {
+    let mut schedule = ScheduleConfig::default();
+    schedule.listing.enabled = false;
+    schedule.key_info.enabled = false;
+    schedule.retention.enabled = false;
+    match job {
+        Job::KeyInfo => schedule.key_info = config,
+        Job::Map => schedule.map = config,
+        Job::Listing => schedule.listing = config,
+        Job::Trending => schedule.trending = config,
+        Job::Quotes => schedule.quotes = config,
+        Job::Info => schedule.info = config,
+        Job::Global => schedule.global = config,
+        Job::Retention => schedule.retention = config,
+    }
+
+    schedule
+}
+
+/// By default, the usage of the API key and the listings are fetched and the retention is applied at
+/// startup, then every hour, every 5 minutes and every hour respectively.
+#[test]
+fn schedule_default_jobs() -> Result<(), CmcError> {
+    let mut scheduler = Scheduler::new(&ScheduleConfig::default(), now())?;
+
+    assert_eq!(scheduler.next(), Some((Job::KeyInfo, now())));
+    scheduler.reschedule(Job::KeyInfo, now());
+    assert_eq!(scheduler.next(), Some((Job::Listing, now())));
+
+    let after_listing = now() + Duration::seconds(10);
+    scheduler.reschedule(Job::Listing, after_listing);
+    assert_eq!(scheduler.next(), Some((Job::Retention, now())));
+    scheduler.reschedule(Job::Retention, after_listing);
+    assert_eq!(
+        scheduler.next(),
+        Some((Job::Listing, after_listing + Duration::minutes(5)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn schedule_cron_job() -> Result<(), CmcError> {
+    let daily = JobConfig {
+        enabled: true,
+        interval: Some(60),
+        cron: Some("0 0 3 * * *".to_string()),
+        jitter: 0,
+    };
+    let mut scheduler = Scheduler::new(&only(Job::Map, daily), now())?;
+
+    let tomorrow = Utc.ymd(2022, 1, 11).and_hms(3, 0, 0);
+    assert_eq!(scheduler.next(), Some((Job::Map, tomorrow)));
+    scheduler.reschedule(Job::Map, tomorrow + Duration::seconds(5));
+    assert_eq!(
+        scheduler.next(),
+        Some((Job::Map, tomorrow + Duration::days(1)))
+    );
+
+    Ok(())
+}
+
+/// Each run is delayed by up to `jitter` seconds.
+#[test]
+fn schedule_jitter() -> Result<(), CmcError> {
+    let jittered = JobConfig {
+        enabled: true,
+        interval: Some(900),
+        cron: None,
+        jitter: 30,
+    };
+    let mut scheduler = Scheduler::new(&only(Job::Global, jittered), now())?;
+
+    for _ in 0..20 {
+        scheduler.reschedule(Job::Global, now());
+        let (_, next) = scheduler.next().unwrap();
+        assert!(next >= now() + Duration::seconds(900));
+        assert!(next <= now() + Duration::seconds(930));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn reject_invalid_schedules() {
+    let job = |interval, cron: Option<&str>| JobConfig {
+        enabled: true,
+        interval,
+        cron: cron.map(str::to_string),
+        jitter: 0,
+    };
+    let invalid = [
+        only(Job::Quotes, job(None, Some("every minute"))),
+        only(Job::Quotes, job(Some(0), None)),
+        only(Job::Quotes, job(None, None)),
+        only(Job::Quotes, JobConfig::default()),
+    ];
+
+    for schedule in &invalid {
+        assert!(matches!(
+            Scheduler::new(schedule, now()),
+            Err(CmcError::InvalidArgument(_))
+        ));
+    }
+}
@@ -1,7 +1,12 @@
 use coin_market_cap::{
-    coin_market::{listing, map, CmcError},
+    coin_market::{
+        categories, category, global_metrics, info, key_info, listing, map, ohlcv_historical,
+        quotes_historical, quotes_latest, trending, App, CmcError,
+    },
     configuration,
     database::*,
+    import::{Dump, ImportSummary},
+    schema_drift,
 };
 use serial_test::serial;
 
@@ -57,6 +62,30 @@ async fn update_crypto_listing_db_1() -> Result<(), CmcError> {
     // Now we can insert into table `crypto_listing`.
     update_crypto_listing(response, pool.clone()).await?;
 
+    // `crypto_listing_latest` keeps the latest snapshot of each cryptocurrency, whatever the order
+    // in which they are inserted.
+    let listing_json = include_str!("data/cryptocurrency_listings_latest_50.json");
+    let response: listing::Response =
+        serde_json::from_str(listing_json).expect("Failed to parse input!");
+    let last_updated = response.data[0].quote.usd.last_updated;
+    for days in [-1, 1] {
+        let mut response: listing::Response =
+            serde_json::from_str(listing_json).expect("Failed to parse input!");
+        for data in &mut response.data {
+            data.quote.usd.last_updated = last_updated + chrono::Duration::days(days);
+        }
+        update_crypto_listing(response, pool.clone()).await?;
+    }
+    let latest: Vec<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT DISTINCT last_updated FROM crypto_listing_latest;")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(latest, [last_updated + chrono::Duration::days(1)]);
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM crypto_listing_latest;")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 50);
+
     Ok(())
 }
 
@@ -89,3 +118,704 @@ async fn update_crypto_populate_db_100_items() -> Result<(), CmcError> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_historical_db_3() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_50.json");
+    let quotes_json = include_str!("data/cryptocurrency_quotes_historical_3.json");
+    let ohlcv_json = include_str!("data/cryptocurrency_ohlcv_historical_3.json");
+
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_quotes: quotes_historical::Response =
+        serde_json::from_str(quotes_json).expect("Failed to parse input!");
+    let response_ohlcv: ohlcv_historical::Response =
+        serde_json::from_str(ohlcv_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    // For consistency (i.e. foreign key constraint), we must first initialize `crypto_map` table.
+    update_crypto_map(response_map, pool.clone()).await?;
+    assert!(crypto_map_contains(1, pool.clone()).await?);
+
+    // Inserting the same history twice must not fail.
+    update_quotes_historical(&response_quotes, pool.clone()).await?;
+    update_quotes_historical(&response_quotes, pool.clone()).await?;
+    update_ohlcv_historical(&response_ohlcv, "daily", pool.clone()).await?;
+    update_ohlcv_historical(&response_ohlcv, "daily", pool.clone()).await?;
+
+    let last = response_quotes.data[&1].quotes[2].timestamp;
+    let endpoint = quotes_historical::ENDPOINT;
+    assert_eq!(
+        get_backfill_progress(1, endpoint, pool.clone()).await?,
+        None
+    );
+    set_backfill_progress(1, endpoint, last, pool.clone()).await?;
+    assert_eq!(get_backfill_progress(1, endpoint, pool).await?, Some(last));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_categories_db_3() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_50.json");
+    let categories_json = include_str!("data/cryptocurrency_categories_3.json");
+    let category_json = include_str!("data/cryptocurrency_category_4.json");
+
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_categories: categories::Response =
+        serde_json::from_str(categories_json).expect("Failed to parse input!");
+    let response_category: category::Response =
+        serde_json::from_str(category_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    // For consistency (i.e. foreign key constraint), we must first initialize `crypto_map` table.
+    update_crypto_map(response_map, pool.clone()).await?;
+    update_categories(&response_categories, pool.clone()).await?;
+    // Replacing the members twice must not fail.
+    update_category_members(&response_category, pool.clone()).await?;
+    update_category_members(&response_category, pool).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_trending_db_5() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_50.json");
+    let trending_json = include_str!("data/cryptocurrency_trending_gainers_losers_5.json");
+
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_trending: trending::Response =
+        serde_json::from_str(trending_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    // For consistency (i.e. foreign key constraint), we must first initialize `crypto_map` table.
+    update_crypto_map(response_map, pool.clone()).await?;
+    let fetched_at = chrono::Utc::now();
+    update_crypto_trending(&response_trending, "gainers", "24h", fetched_at, pool).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_map_inactive_db_3() -> Result<(), CmcError> {
+    let active_json = include_str!("data/cryptocurrency_map_50.json");
+    let inactive_json = include_str!("data/cryptocurrency_map_inactive_untracked_3.json");
+
+    let response_active: map::Response =
+        serde_json::from_str(active_json).expect("Failed to parse input!");
+    let response_inactive: map::Response =
+        serde_json::from_str(inactive_json).expect("Failed to parse input!");
+    let delisted_id = response_inactive.data[0].id as i32;
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response_active, pool.clone()).await?;
+    update_crypto_map(response_inactive, pool.clone()).await?;
+
+    // The delisted cryptocurrency is kept along with the timestamp of its delisting.
+    let (is_active, delisted_at): (Option<bool>, Option<chrono::DateTime<chrono::Utc>>) =
+        sqlx::query_as("SELECT is_active, delisted_at FROM crypto_map WHERE id = $1;")
+            .bind(delisted_id)
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(is_active, Some(false));
+    assert!(delisted_at.is_some());
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM crypto_map;")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 52);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_key_usage_db() -> Result<(), CmcError> {
+    let str_json = include_str!("data/key_info.json");
+    let response: key_info::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_key_usage(&response, chrono::Utc::now(), pool.clone()).await?;
+
+    let credits_left: i32 = sqlx::query_scalar("SELECT credits_left_month FROM key_usage;")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(credits_left, 119968);
+
+    Ok(())
+}
+
+/// Snapshots of the global metrics are stored once per `last_updated`.
+#[tokio::test]
+#[serial]
+async fn update_global_metrics_db() -> Result<(), CmcError> {
+    let str_json = include_str!("data/global_metrics_quotes_latest.json");
+    let response: global_metrics::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_global_metrics(&response.data, pool.clone()).await?;
+    update_global_metrics(&response.data, pool.clone()).await?;
+
+    let (snapshots, active): (i64, i32) =
+        sqlx::query_as("SELECT COUNT(*), MAX(active_cryptocurrencies) FROM global_metrics;")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!((snapshots, active), (1, 6723));
+
+    Ok(())
+}
+
+/// The quotes of a watchlist are appended to `crypto_listing` like the listings.
+#[tokio::test]
+#[serial]
+async fn insert_crypto_quotes_db_2() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_100_ranked.json");
+    let quotes_json = include_str!("data/cryptocurrency_quotes_latest_2.json");
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_quotes: quotes_latest::Response =
+        serde_json::from_str(quotes_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response_map, pool.clone()).await?;
+    let data: Vec<listing::Data> = response_quotes.data.into_values().collect();
+    let rows = insert_crypto_listings(&data, pool.clone()).await?;
+    assert_eq!(rows.inserted, 2);
+
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT tag FROM crypto_tag WHERE id = 1 ORDER BY tag;")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(tags, ["mineable", "pow", "sha-256"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_listing_nulls_db_3() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_50.json");
+    let listing_json = include_str!("data/cryptocurrency_listings_latest_nulls_3.json");
+
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_listing: listing::Response =
+        serde_json::from_str(listing_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response_map, pool.clone()).await?;
+    update_crypto_listing(response_listing, pool.clone()).await?;
+
+    // The listing without a price is skipped, while the rest are stored with their missing fields.
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM crypto_listing;")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_listing_overflow_db_1() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_50.json");
+    let listing_json = include_str!("data/cryptocurrency_listings_latest_overflow_1.json");
+
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_listing: listing::Response =
+        serde_json::from_str(listing_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response_map, pool.clone()).await?;
+    update_crypto_listing(response_listing, pool.clone()).await?;
+
+    // The values are stored as `NUMERIC` without any loss of precision.
+    let total_supply: String = sqlx::query_scalar("SELECT total_supply::TEXT FROM crypto_listing;")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(
+        total_supply,
+        "999999999999999999999999999999999999.123456789"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_schema_drift_db() -> Result<(), CmcError> {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_ignored_fields.json");
+    let value: serde_json::Value = serde_json::from_str(str_json).expect("Failed to parse input!");
+    let (_, drift): (listing::Response, _) = schema_drift::deserialize(&value)?;
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    let endpoint = "/v1/cryptocurrency/listings/latest";
+    let now = chrono::Utc::now();
+
+    // The drift is only new the first time it is recorded.
+    let new = update_schema_drift(endpoint, &drift, now, pool.clone()).await?;
+    assert_eq!(new, drift);
+    let new = update_schema_drift(endpoint, &drift, now, pool.clone()).await?;
+    assert!(new.is_empty());
+
+    let occurrences: i32 = sqlx::query_scalar(
+        "SELECT occurrences FROM schema_drift WHERE path = 'status.ignored_field_1';",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(occurrences, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn get_missing_crypto_map_ids_db() -> Result<(), CmcError> {
+    let str_json = include_str!("data/cryptocurrency_map_50.json");
+    let response: map::Response = serde_json::from_str(str_json).expect("Failed to parse input!");
+    let id = response.data[0].id;
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response, pool.clone()).await?;
+
+    let missing = get_missing_crypto_map_ids(&[id, u32::MAX >> 1], pool).await?;
+    assert_eq!(missing, [u32::MAX >> 1]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_token_contracts_db_3() -> Result<(), CmcError> {
+    let map_json = include_str!("data/cryptocurrency_map_50.json");
+    let info_json = include_str!("data/cryptocurrency_info_3.json");
+
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_info: info::Response =
+        serde_json::from_str(info_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response_map, pool.clone()).await?;
+
+    // The map refers to the base chain on which each token was issued, not to the token itself.
+    let platform: String = sqlx::query_scalar(
+        "SELECT p.name FROM crypto_map m JOIN platform p ON p.id = m.platform WHERE m.id = $1;",
+    )
+    .bind(4687)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(platform, "Binance Chain (BEP2)");
+
+    update_token_contracts(&response_info, pool.clone()).await?;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM token_contract WHERE id = $1;")
+        .bind(825)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 4);
+
+    // BEP2 and BEP20 share the same native coin, but they are different platforms.
+    let id: i32 = sqlx::query_scalar(
+        r#"SELECT t.id FROM token_contract t JOIN platform p ON p.id = t.platform_id
+           WHERE p.name = $1 AND lower(t.contract_address) = lower($2);"#,
+    )
+    .bind("Binance Smart Chain (BEP20)")
+    .bind("0xE9e7CEA3DedcA5984780Bafc599bD69ADd087D56")
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(id, 4687);
+
+    let platforms: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM platform WHERE coin_id = 1839;")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(platforms, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn update_crypto_alias_db_1() -> Result<(), CmcError> {
+    let str_json = include_str!("data/cryptocurrency_map_50.json");
+    let response: map::Response = serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+
+    clear_all_tables(pool.clone()).await?;
+    update_crypto_map(response, pool.clone()).await?;
+
+    // Rebrand Tether a day later.
+    let mut response: map::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+    response.status.timestamp = response.status.timestamp + chrono::Duration::days(1);
+    let tether = response
+        .data
+        .iter_mut()
+        .find(|data| data.id == 825)
+        .expect("Tether not found!");
+    tether.symbol = "USDT2".to_string();
+    // A change of rank alone isn't an alias.
+    let bitcoin = response
+        .data
+        .iter_mut()
+        .find(|data| data.id == 1)
+        .expect("Bitcoin not found!");
+    bitcoin.rank = Some(2);
+    let rebranded_at = response.status.timestamp;
+    update_crypto_map(response, pool.clone()).await?;
+
+    // Importing the older snapshot again doesn't roll the map back.
+    let response: map::Response = serde_json::from_str(str_json).expect("Failed to parse input!");
+    update_crypto_map(response, pool.clone()).await?;
+    let symbol: String = sqlx::query_scalar("SELECT symbol FROM crypto_map WHERE id = $1;")
+        .bind(825)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(symbol, "USDT2");
+
+    let aliases: Vec<(String, Option<chrono::DateTime<chrono::Utc>>)> = sqlx::query_as(
+        "SELECT symbol, valid_to FROM crypto_alias WHERE id = $1 ORDER BY valid_from;",
+    )
+    .bind(825)
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(
+        aliases,
+        [
+            ("USDT".to_string(), Some(rebranded_at)),
+            ("USDT2".to_string(), None)
+        ]
+    );
+
+    // Unchanged cryptocurrencies keep a single row.
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM crypto_alias WHERE id = $1;")
+        .bind(1)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn migrate_db() -> Result<(), CmcError> {
+    let config = configuration::load_config()?;
+
+    // Creates the database if missing, while migrating it again is a no-op.
+    migrate(&config.database).await?;
+    migrate(&config.database).await?;
+
+    let pool = get_connection_pool(&config.database);
+    assert!(get_pending_migrations(pool).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn import_file_db() -> Result<(), CmcError> {
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+    let app = App::new();
+
+    clear_all_tables(pool.clone()).await?;
+    let data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    app.import(&data.join("cryptocurrency_map_50.json"), Some(Dump::Map))
+        .await?;
+    // Only the listings of the cryptocurrencies in the map are imported.
+    app.import(
+        &data.join("cryptocurrency_listings_latest_100.json"),
+        Some(Dump::Listing),
+    )
+    .await?;
+
+    let status = app.cache_status().await?;
+    assert_eq!(status.cryptocurrencies, 50);
+    assert_eq!(status.listings, 50);
+    assert!(status.last_listing.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn import_bundle_db() -> Result<(), CmcError> {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+    let app = App::new();
+
+    let data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let dir = std::env::temp_dir().join(format!("cmc-import-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("2021"))?;
+    // The listings come first, but are only imported after the map.
+    let mut bundle = GzEncoder::new(
+        std::fs::File::create(dir.join("2021/listings.ndjson.gz"))?,
+        Compression::default(),
+    );
+    for name in [
+        "cryptocurrency_listings_latest_4.json",
+        "cryptocurrency_listings_latest_100.json",
+    ] {
+        let snapshot: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(data.join(name))?)?;
+        writeln!(bundle, "{}", snapshot)?;
+    }
+    writeln!(bundle, "{{\"data\": [{{}}]}}")?;
+    writeln!(bundle, "{{\"truncated")?;
+    bundle.finish()?;
+    std::fs::copy(
+        data.join("cryptocurrency_map_50.json"),
+        dir.join("map.json"),
+    )?;
+    std::fs::write(dir.join("README.txt"), "not a dump")?;
+
+    clear_all_tables(pool.clone()).await?;
+    let summary = app.import(&dir, None).await;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(
+        summary?,
+        ImportSummary {
+            maps: 1,
+            listings: 2,
+            failed: 2,
+        }
+    );
+    let status = app.cache_status().await?;
+    assert_eq!(status.cryptocurrencies, 50);
+    assert_eq!(status.listings, 54);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn quarantine_invalid_listings_db() -> Result<(), CmcError> {
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+    let app = App::new();
+
+    clear_all_tables(pool.clone()).await?;
+    let data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    app.import(&data.join("cryptocurrency_map_50.json"), Some(Dump::Map))
+        .await?;
+    app.import(
+        &data.join("cryptocurrency_listings_latest_4.json"),
+        Some(Dump::Listing),
+    )
+    .await?;
+
+    // A later snapshot where BTC is free, ETH jumped by about 20 000% and BNB exceeds its max supply.
+    let mut snapshot: serde_json::Value =
+        serde_json::from_str(include_str!("data/cryptocurrency_listings_latest_4.json"))?;
+    for data in snapshot["data"].as_array_mut().unwrap() {
+        data["quote"]["USD"]["last_updated"] = "2021-10-12T17:45:00Z".into();
+    }
+    snapshot["data"][0]["quote"]["USD"]["price"] = "0".into();
+    snapshot["data"][1]["quote"]["USD"]["price"] = "700000".into();
+    snapshot["data"][2]["circulating_supply"] = "200000000".into();
+    let path = std::env::temp_dir().join(format!("cmc-quarantine-{}.json", std::process::id()));
+    std::fs::write(&path, snapshot.to_string())?;
+    let imported = app.import(&path, Some(Dump::Listing)).await;
+    std::fs::remove_file(&path)?;
+    imported?;
+
+    // Only ADA made it to the listings.
+    let status = app.cache_status().await?;
+    assert_eq!(status.listings, 5);
+    let quarantined: Vec<(i32, Vec<String>)> =
+        sqlx::query_as("SELECT id, reasons FROM crypto_listing_quarantine ORDER BY id;")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(
+        quarantined,
+        [
+            (1, vec!["zero price".to_string()]),
+            (
+                1027,
+                vec!["price changed by 19970% since the previous snapshot".to_string()]
+            ),
+            (
+                1839,
+                vec!["circulating_supply above max_supply".to_string()]
+            ),
+        ]
+    );
+
+    // The next snapshots are compared to the quarantined ones, so the new price of ETH holds.
+    for data in snapshot["data"].as_array_mut().unwrap() {
+        data["quote"]["USD"]["last_updated"] = "2021-10-12T17:50:00Z".into();
+    }
+    snapshot["data"][0]["quote"]["USD"]["price"] = "57000".into();
+    snapshot["data"][1]["quote"]["USD"]["price"] = "700100".into();
+    snapshot["data"][2]["circulating_supply"] = "166801148".into();
+    std::fs::write(&path, snapshot.to_string())?;
+    let imported = app.import(&path, Some(Dump::Listing)).await;
+    std::fs::remove_file(&path)?;
+    imported?;
+
+    let status = app.cache_status().await?;
+    assert_eq!(status.listings, 9);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn apply_retention_db() -> Result<(), CmcError> {
+    use bigdecimal::BigDecimal;
+    use chrono::{TimeZone, Utc};
+    use coin_market_cap::configuration::RetentionConfig;
+    use coin_market_cap::retention::{self, partition_month};
+    use std::str::FromStr;
+
+    let config = configuration::load_config()?;
+    let pool = get_connection_pool(&config.database);
+    let app = App::new();
+
+    clear_all_tables(pool.clone()).await?;
+    let data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    app.import(&data.join("cryptocurrency_map_50.json"), Some(Dump::Map))
+        .await?;
+    app.import(
+        &data.join("cryptocurrency_listings_latest_4.json"),
+        Some(Dump::Listing),
+    )
+    .await?;
+    // Two later snapshots of BTC, in the same hour and in the next one.
+    let snapshot: serde_json::Value =
+        serde_json::from_str(include_str!("data/cryptocurrency_listings_latest_4.json"))?;
+    let path = std::env::temp_dir().join(format!("cmc-retention-{}.ndjson", std::process::id()));
+    let mut dump = String::new();
+    for (last_updated, price) in [
+        ("2021-10-12T17:45:00Z", "56000"),
+        ("2021-10-12T18:05:00Z", "55000"),
+    ] {
+        let mut snapshot = snapshot.clone();
+        snapshot["data"] = serde_json::json!([snapshot["data"][0].clone()]);
+        snapshot["data"][0]["quote"]["USD"]["last_updated"] = last_updated.into();
+        snapshot["data"][0]["quote"]["USD"]["price"] = price.into();
+        dump.push_str(&format!("{}\n", snapshot));
+    }
+    std::fs::write(&path, dump)?;
+    let imported = app.import(&path, Some(Dump::Listing)).await;
+    std::fs::remove_file(&path)?;
+    imported?;
+
+    // The snapshots of October 2021 land in the default partition, until the retention creates
+    // theirs, rolls it up and drops it.
+    let retention = RetentionConfig {
+        raw_max_age: Some(90),
+        ..RetentionConfig::default()
+    };
+    let summary = retention::apply(&retention, Utc::now(), pool.clone()).await?;
+    assert!(summary.partitions_created >= 1);
+    assert_eq!(summary.partitions_dropped, ["crypto_listing_2021_10"]);
+    assert_eq!(app.cache_status().await?.listings, 0);
+    assert!(get_listing_partitions(pool.clone()).await?.len() >= 2);
+
+    let decimal = |s| BigDecimal::from_str(s).unwrap();
+    let btc = |table| {
+        format!(
+            "SELECT open, high, low, close, snapshots FROM {} WHERE id = 1 ORDER BY bucket;",
+            table
+        )
+    };
+    let hourly: Vec<(BigDecimal, BigDecimal, BigDecimal, BigDecimal, i32)> =
+        sqlx::query_as(&btc("crypto_listing_hourly"))
+            .fetch_all(&pool)
+            .await?;
+    let first = decimal("55684.44009567926");
+    assert_eq!(
+        hourly,
+        [
+            (
+                first.clone(),
+                decimal("56000"),
+                first.clone(),
+                decimal("56000"),
+                2
+            ),
+            (
+                decimal("55000"),
+                decimal("55000"),
+                decimal("55000"),
+                decimal("55000"),
+                1
+            ),
+        ]
+    );
+    let daily: Vec<(BigDecimal, BigDecimal, BigDecimal, BigDecimal, i32)> =
+        sqlx::query_as(&btc("crypto_listing_daily"))
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(
+        daily,
+        [(
+            first,
+            decimal("56000"),
+            decimal("55000"),
+            decimal("55000"),
+            3
+        )]
+    );
+
+    assert_eq!(
+        partition_month("crypto_listing_2021_12"),
+        Some(Utc.ymd(2021, 12, 1).and_hms(0, 0, 0))
+    );
+    assert_eq!(partition_month("crypto_listing_default"), None);
+
+    Ok(())
+}
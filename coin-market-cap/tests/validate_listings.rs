@@ -0,0 +1,86 @@
+use bigdecimal::BigDecimal;
+use coin_market_cap::coin_market::listing;
+use coin_market_cap::configuration::ValidationConfig;
+use coin_market_cap::validation::{check, validate, Violation};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Listings of BTC, ETH, BNB and ADA.
+fn listings() -> Vec<listing::Data> {
+    let str_json = include_str!("data/cryptocurrency_listings_latest_4.json");
+    let response: listing::Response =
+        serde_json::from_str(str_json).expect("Failed to parse input!");
+
+    response.data
+}
+
+fn decimal(s: &str) -> BigDecimal {
+    BigDecimal::from_str(s).unwrap()
+}
+
+#[test]
+fn accept_valid_listings() {
+    let previous = HashMap::from([(1, decimal("50000")), (1027, decimal("3000"))]);
+    let validated = validate(listings(), &previous, &ValidationConfig::default());
+
+    assert_eq!(validated.valid.len(), 4);
+    assert!(validated.invalid.is_empty());
+}
+
+#[test]
+fn detect_invalid_listings() {
+    let config = ValidationConfig::default();
+    let mut data = listings();
+    data[0].quote.usd.price = Some(decimal("0"));
+    data[1].total_supply = Some(decimal("-1"));
+    data[2].circulating_supply = Some(decimal("200000000"));
+
+    assert_eq!(check(&data[0], None, &config), [Violation::ZeroPrice]);
+    assert_eq!(
+        check(&data[1], None, &config),
+        [Violation::NegativeSupply("total_supply")]
+    );
+    assert_eq!(check(&data[2], None, &config), [Violation::SupplyAboveMax]);
+    // ADA was at 0.01 before, i.e. a jump of about 21 240%.
+    let violations = check(&data[3], Some(&decimal("0.01")), &config);
+    assert!(matches!(violations[..], [Violation::PriceJump(_)]));
+    assert_eq!(
+        violations[0].to_string(),
+        "price changed by 21240% since the previous snapshot"
+    );
+    assert!(check(&data[3], Some(&decimal("0.1")), &config).is_empty());
+    // A fall from 1.0 to 0.0001, i.e. to a ten thousandth.
+    data[3].quote.usd.price = Some(decimal("0.0001"));
+    let violations = check(&data[3], Some(&decimal("1.0")), &config);
+    assert!(matches!(violations[..], [Violation::PriceJump(_)]));
+    assert_eq!(
+        violations[0].to_string(),
+        "price changed by 999900% since the previous snapshot"
+    );
+    assert!(check(&data[3], Some(&decimal("0.001")), &config).is_empty());
+}
+
+#[test]
+fn disable_rules() {
+    let config = ValidationConfig {
+        zero_price: false,
+        supply_above_max: false,
+        max_price_change: None,
+        ..ValidationConfig::default()
+    };
+    let mut data = listings();
+    data[0].quote.usd.price = Some(decimal("0"));
+    data[0].circulating_supply = Some(decimal("22000000"));
+    data[1].max_supply = Some(decimal("-1"));
+    let previous = HashMap::from([(2010, decimal("0.01"))]);
+
+    let validated = validate(data, &previous, &config);
+    assert_eq!(validated.valid.len(), 3);
+    assert_eq!(validated.invalid.len(), 1);
+    assert_eq!(validated.invalid[0].data.id, 1027);
+    assert_eq!(validated.invalid[0].reasons(), ["negative max_supply"]);
+    assert_eq!(
+        validated.violations().into_iter().collect::<Vec<_>>(),
+        [("negative_supply", 1)]
+    );
+}
@@ -2,12 +2,18 @@ use sqlx::PgPool;
 use std::net::TcpListener;
 use thiserror::Error;
 
-use crate::route::{crypto_listing, crypto_map, health_check};
+use crate::freshness::Freshness;
+use crate::route::{
+    admin, crypto_category, crypto_convert, crypto_listing, crypto_map, crypto_platform,
+    crypto_trending, health_check,
+};
+use crate::upstream::PriceConversion;
 use crate::{config, database};
 use actix_web::{
     dev::Server,
+    http::StatusCode,
     web::{self, Data},
-    App, HttpServer,
+    App, HttpServer, ResponseError,
 };
 
 pub struct CfxServer {
@@ -16,13 +22,25 @@ pub struct CfxServer {
 }
 
 impl CfxServer {
-    pub async fn build(config: config::Configuration) -> Result<Self, std::io::Error> {
+    /// Builds the server once the database is ready. If enabled in the `database` section of the
+    /// configuration, the database is created and migrated first. Then, it refuses to start if the
+    /// schema is older than the one expected by the binary.
+    pub async fn build(config: config::Configuration) -> Result<Self, CfxError> {
+        if config.database.migrate {
+            database::migrate(&config.database).await?;
+        }
         let db_pool = database::get_connection_pool(&config.database);
+        let pending = database::get_pending_migrations(db_pool.clone()).await?;
+        if !pending.is_empty() {
+            return Err(CfxError::OutdatedSchema(pending));
+        }
 
         let address = format!("{}:{}", config.application.host, config.application.port);
         let listener = TcpListener::bind(&address)?;
         let port = listener.local_addr().unwrap().port();
-        let server = setup(listener, db_pool)?;
+        let upstream = config.upstream.map(PriceConversion::new);
+        let freshness = Freshness::new(config.freshness);
+        let server = setup(listener, db_pool, upstream, freshness, config.admin)?;
 
         Ok(Self { port, server })
     }
@@ -42,16 +60,88 @@ pub enum CfxError {
     CfgError(#[from] ::config::ConfigError),
     #[error("Issues with I/O operations")]
     IoError(#[from] std::io::Error),
+    #[error("Issues querying the database")]
+    DbQuery(#[from] sqlx::Error),
+    #[error("Issues migrating the database")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error("The database schema is outdated, pending migrations: {0:?}")]
+    OutdatedSchema(Vec<i64>),
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Missing or invalid token")]
+    Unauthorized,
+    #[error("Issues during the request to the upstream server")]
+    Request(#[from] reqwest::Error),
+    #[error("The upstream server replied with {0}")]
+    Upstream(String),
+    #[error("{0} is stale")]
+    Stale(String),
 }
 
-fn setup(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Error> {
+impl ResponseError for CfxError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CfxError::NotFound(_) => StatusCode::NOT_FOUND,
+            CfxError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            CfxError::Unauthorized => StatusCode::UNAUTHORIZED,
+            CfxError::Request(_) | CfxError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            CfxError::Stale(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn setup(
+    listener: TcpListener,
+    db_pool: PgPool,
+    upstream: Option<PriceConversion>,
+    freshness: Freshness,
+    admin: Option<config::AdminConfig>,
+) -> Result<Server, std::io::Error> {
     let pool = Data::new(db_pool);
+    let upstream = Data::new(upstream);
+    let freshness = Data::new(freshness);
+    let admin_config = admin.map(Data::new);
     let server = HttpServer::new(move || {
-        App::new()
-            .route("/health_check", web::get().to(health_check::health_check))
+        let mut app = App::new();
+        // The admin endpoints are only served once a token is configured.
+        if let Some(admin_config) = &admin_config {
+            app = app
+                .route("/admin/ingestion", web::get().to(admin::ingestion))
+                .app_data(admin_config.clone());
+        }
+
+        app.route("/health_check", web::get().to(health_check::health_check))
             .route("/crypto/map/", web::get().to(crypto_map::map))
+            .route("/crypto/{id}/aliases", web::get().to(crypto_map::aliases))
             .route("/crypto/listing/", web::get().to(crypto_listing::listing))
+            .route(
+                "/crypto/category/",
+                web::get().to(crypto_category::categories),
+            )
+            .route(
+                "/crypto/category/{id}",
+                web::get().to(crypto_category::category),
+            )
+            .route("/crypto/movers", web::get().to(crypto_trending::movers))
+            .route(
+                "/crypto/trending/{feed}",
+                web::get().to(crypto_trending::trending),
+            )
+            .route("/crypto/convert", web::get().to(crypto_convert::convert))
+            .route(
+                "/crypto/platform/",
+                web::get().to(crypto_platform::platforms),
+            )
+            .route(
+                "/crypto/contract/{chain}/{address}",
+                web::get().to(crypto_platform::contract),
+            )
             .app_data(pool.clone())
+            .app_data(upstream.clone())
+            .app_data(freshness.clone())
     })
     .listen(listener)?
     .run();
@@ -1,10 +1,12 @@
 //! Manage the application configuration hierarchically using the content of `config` directory.
 
-use serde::Deserialize;
-use sqlx::postgres::PgConnectOptions;
-use sqlx::ConnectOptions;
+use actix_web::http::StatusCode;
+use serde::{Deserialize, Deserializer};
 use std::convert::{TryFrom, TryInto};
 
+/// Same settings as those of `coin-market-cap`, whose database is shared.
+pub use cmc_common::config::DbConfig;
+
 /// Possible runtime environment of the application.
 pub enum Env {
     Development,
@@ -39,6 +41,15 @@ impl TryFrom<String> for Env {
 pub struct Configuration {
     pub database: DbConfig,
     pub application: AppConfig,
+    /// CoinMarketCap's price conversion tool, used as a fallback when a conversion can't be priced
+    /// from the local cache. Disabled if missing.
+    #[serde(default)]
+    pub upstream: Option<UpstreamConfig>,
+    #[serde(default)]
+    pub freshness: FreshnessConfig,
+    /// Admin endpoints, e.g. `/admin/ingestion`. Not served if missing.
+    #[serde(default)]
+    pub admin: Option<AdminConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -48,27 +59,69 @@ pub struct AppConfig {
 }
 
 #[derive(Debug, Clone, Deserialize)]
-pub struct DbConfig {
-    pub username: String,
-    pub password: String,
-    pub port: u16,
-    pub host: String,
-    pub database_name: String,
+pub struct UpstreamConfig {
+    pub base_url: String,
+    pub api_key: String,
+    /// Number of seconds the upstream rates are cached.
+    pub cache_ttl: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminConfig {
+    /// Token expected in the `Authorization: Bearer <token>` header of the admin requests.
+    pub token: String,
+}
+
+/// Maximum age of the served data before it's flagged as stale (see `freshness`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FreshnessConfig {
+    pub max_age: MaxAgeConfig,
+    /// Whether to refuse the conversions priced from stale listings, unless the upstream price
+    /// conversion tool can price them instead.
+    pub refuse_stale_conversions: bool,
+    /// Status of the `/health_check` responses once any type of data is stale.
+    #[serde(deserialize_with = "status_code")]
+    pub degraded_status: StatusCode,
 }
 
-impl DbConfig {
-    pub fn without_db(&self) -> PgConnectOptions {
-        PgConnectOptions::new()
-            .host(&self.host)
-            .username(&self.username)
-            .password(&self.password)
-            .port(self.port)
+impl Default for FreshnessConfig {
+    fn default() -> Self {
+        Self {
+            max_age: MaxAgeConfig::default(),
+            refuse_stale_conversions: false,
+            degraded_status: StatusCode::SERVICE_UNAVAILABLE,
+        }
     }
+}
+
+/// Deserializes an HTTP status code from its number, e.g. `503`.
+fn status_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StatusCode, D::Error> {
+    let code = u16::deserialize(deserializer)?;
+    StatusCode::from_u16(code).map_err(serde::de::Error::custom)
+}
 
-    pub fn with_db(&self) -> PgConnectOptions {
-        let mut options = self.without_db().database(&self.database_name);
-        options.log_statements(log::LevelFilter::Trace);
-        options
+/// Maximum age in seconds of each type of data, which is never stale if missing. Only the data
+/// types with a maximum age are checked by `/health_check`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MaxAgeConfig {
+    /// Prices and market data of the listings (and quotes).
+    pub listing: Option<u64>,
+    /// Snapshots of the trending feeds.
+    pub trending: Option<u64>,
+    /// Coin categories.
+    pub category: Option<u64>,
+}
+
+impl Default for MaxAgeConfig {
+    fn default() -> Self {
+        Self {
+            // Three polls of the listings with the default schedule of `coin-market-cap`.
+            listing: Some(900),
+            trending: None,
+            category: None,
+        }
     }
 }
 
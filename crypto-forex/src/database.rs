@@ -1,27 +1,276 @@
+use chrono::prelude::*;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
+pub use cmc_common::database::{get_pending_migrations, migrate, MIGRATOR};
+
 use crate::config::DbConfig;
+use crate::freshness::DataKind;
 use crate::model::*;
 
 /// Returns all rows in table `crypto_map` as a `Vec<CryptoMap>`.
 pub async fn get_crypto_map(pool: &PgPool) -> Result<Vec<CryptoMap>, sqlx::Error> {
-    Ok(sqlx::query_as("SELECT * FROM crypto_map;")
+    sqlx::query_as("SELECT * FROM crypto_map;")
         .fetch_all(pool)
-        .await?)
+        .await
 }
 
-/// Returns all rows in table `crypto_listing` as a `Vec<CryptoListing>`.
-pub async fn get_crypto_listing(pool: &PgPool) -> Result<Vec<CryptoListing>, sqlx::Error> {
-    Ok(sqlx::query_as("SELECT * FROM crypto_listing;")
-        .fetch_all(pool)
-        .await?)
+/// Returns the most recent runs in table `ingest_run` (newest first), optionally filtered by
+/// `endpoint` and `status` (either `running`, `succeeded` or `failed`).
+pub async fn get_ingest_runs(
+    pool: &PgPool,
+    endpoint: Option<&str>,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<IngestRun>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT * FROM (
+               SELECT id, endpoint, params,
+                      CASE WHEN finished_at IS NULL THEN 'running'
+                           WHEN error IS NULL THEN 'succeeded'
+                           ELSE 'failed'
+                      END AS status,
+                      started_at, finished_at, rows_inserted, rows_updated,
+                      rows_rejected, rows_quarantined, credits_used, error
+               FROM ingest_run
+               WHERE $1::TEXT IS NULL OR endpoint = $1
+           ) AS run
+           WHERE $2::TEXT IS NULL OR status = $2
+           ORDER BY started_at DESC, id DESC
+           LIMIT $3;"#,
+    )
+    .bind(endpoint)
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the history of the names, symbols, slugs and ranks of the cryptocurrency `id` in table
+/// `crypto_alias`, from the oldest to the current values.
+pub async fn get_crypto_aliases(pool: &PgPool, id: i32) -> Result<Vec<CryptoAlias>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT name, symbol, slug, rank, valid_from, valid_to FROM crypto_alias
+           WHERE id = $1
+           ORDER BY valid_from;"#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
 }
 
-/// Returns all rows in table `crypto_platform` as a `Vec<CryptoPlatform>`.
-pub async fn get_crypto_platform(pool: &PgPool) -> Result<Vec<CryptoPlatform>, sqlx::Error> {
-    Ok(sqlx::query_as("SELECT * FROM crypto_platform;")
+/// Returns the latest snapshot in table `crypto_listing_latest` of the cryptocurrency ranked
+/// `cmc_rank`, if any. Should a rank be held by several cryptocurrencies (i.e. the snapshot of a
+/// former holder is outdated), the most recently updated one is returned.
+pub async fn get_crypto_listing_by_rank(
+    pool: &PgPool,
+    cmc_rank: i32,
+) -> Result<Option<CryptoListing>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT * FROM crypto_listing_latest
+           WHERE cmc_rank = $1
+           ORDER BY last_updated DESC
+           LIMIT 1;"#,
+    )
+    .bind(cmc_rank)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns all rows in table `platform`, ordered by name, as a `Vec<Platform>`.
+pub async fn get_platforms(pool: &PgPool) -> Result<Vec<Platform>, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM platform ORDER BY name;")
         .fetch_all(pool)
-        .await?)
+        .await
+}
+
+/// Returns the row in table `crypto_map` of the token deployed at `contract_address` on `chain`,
+/// which is either the id, the name or the slug of the platform. Both are case-insensitive.
+pub async fn get_token_by_contract(
+    pool: &PgPool,
+    chain: &str,
+    contract_address: &str,
+) -> Result<Option<CryptoMap>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT m.* FROM token_contract t
+           JOIN platform p ON p.id = t.platform_id
+           JOIN crypto_map m ON m.id = t.id
+           WHERE (p.id::TEXT = $1 OR lower(p.name) = lower($1) OR lower(p.slug) = lower($1))
+             AND lower(t.contract_address) = lower($2)
+           ORDER BY m.rank NULLS LAST
+           LIMIT 1;"#,
+    )
+    .bind(chain)
+    .bind(contract_address)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns the contracts of the token `id` in table `token_contract`, ordered by platform.
+pub async fn get_token_contracts(
+    pool: &PgPool,
+    id: i32,
+) -> Result<Vec<TokenContract>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT t.platform_id, p.name AS platform, t.contract_address
+           FROM token_contract t
+           JOIN platform p ON p.id = t.platform_id
+           WHERE t.id = $1
+           ORDER BY p.name;"#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns all rows in table `category` along with the aggregate market cap of their members,
+/// ordered by the latter.
+pub async fn get_categories(pool: &PgPool) -> Result<Vec<Category>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT c.id, c.name, c.title, c.description, c.num_tokens, c.last_updated,
+                  SUM(latest.market_cap) AS market_cap
+           FROM category c
+           LEFT JOIN crypto_category cc ON cc.category_id = c.id
+           LEFT JOIN crypto_listing_latest latest ON latest.id = cc.crypto_id
+           GROUP BY c.id
+           ORDER BY market_cap DESC NULLS LAST;"#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the category `id` along with the aggregate market cap of its members, if any.
+pub async fn get_category(pool: &PgPool, id: &str) -> Result<Option<Category>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT c.id, c.name, c.title, c.description, c.num_tokens, c.last_updated,
+                  SUM(latest.market_cap) AS market_cap
+           FROM category c
+           LEFT JOIN crypto_category cc ON cc.category_id = c.id
+           LEFT JOIN crypto_listing_latest latest ON latest.id = cc.crypto_id
+           WHERE c.id = $1
+           GROUP BY c.id;"#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns the rows in table `crypto_map` that belong to the category `id`, ordered by rank.
+pub async fn get_category_members(pool: &PgPool, id: &str) -> Result<Vec<CryptoMap>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT m.* FROM crypto_map m
+           JOIN crypto_category cc ON cc.crypto_id = m.id
+           WHERE cc.category_id = $1
+           ORDER BY m.rank;"#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the latest snapshot of the trending `feed` (i.e. `gainers`, `losers`, `latest` or
+/// `most_visited`) over `time_period` in table `crypto_trending`, unless it was fetched before
+/// `since`.
+pub async fn get_crypto_trending(
+    pool: &PgPool,
+    feed: &str,
+    time_period: &str,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Mover>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT t.id, m.name, m.symbol, t.price, t.percent_change, t.last_updated
+           FROM crypto_trending t
+           JOIN crypto_map m ON m.id = t.id
+           WHERE t.feed = $1 AND t.time_period = $2 AND t.fetched_at >= $3
+             AND t.fetched_at = (
+                 SELECT MAX(fetched_at) FROM crypto_trending WHERE feed = $1 AND time_period = $2
+             )
+           ORDER BY t.rank
+           LIMIT $4;"#,
+    )
+    .bind(feed)
+    .bind(time_period)
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the biggest gainers (or losers if `gainers` is `false`) according to the column
+/// `percent_change` (e.g. `percent_change_24h`) of the latest snapshot of each cryptocurrency in
+/// table `crypto_listing_latest`.
+///
+/// **Warning:** `percent_change` is interpolated into the query, so it must never come from user
+/// input.
+pub async fn get_crypto_movers(
+    pool: &PgPool,
+    percent_change: &'static str,
+    gainers: bool,
+    limit: i64,
+) -> Result<Vec<Mover>, sqlx::Error> {
+    let (sign, order) = if gainers { (">", "DESC") } else { ("<", "ASC") };
+    let query = format!(
+        r#"SELECT l.id, m.name, m.symbol, l.price, l.{column} AS percent_change, l.last_updated
+           FROM crypto_listing_latest l
+           JOIN crypto_map m ON m.id = l.id
+           WHERE l.{column} {sign} 0
+           ORDER BY l.{column} {order}
+           LIMIT $1;"#,
+        column = percent_change,
+        sign = sign,
+        order = order
+    );
+
+    sqlx::query_as(&query).bind(limit).fetch_all(pool).await
+}
+
+/// Returns the latest USD price of `asset` in table `crypto_listing` updated at or before `until`
+/// (and not before `since`, if given). Symbols are resolved against the history in `crypto_alias`,
+/// preferring the cryptocurrencies that held the symbol at `until`.
+pub async fn get_crypto_price(
+    pool: &PgPool,
+    asset: &Asset,
+    since: Option<DateTime<Utc>>,
+    until: DateTime<Utc>,
+) -> Result<Option<Price>, sqlx::Error> {
+    let (id, symbol) = match asset {
+        Asset::Id(id) => (Some(*id), None),
+        Asset::Symbol(symbol) => (None, Some(symbol.as_str())),
+    };
+
+    sqlx::query_as(
+        r#"SELECT l.id, m.symbol, l.price, l.last_updated
+           FROM crypto_listing l
+           JOIN crypto_map m ON m.id = l.id
+           LEFT JOIN crypto_alias a ON a.id = m.id AND a.symbol = $2
+                AND a.valid_from <= $4 AND (a.valid_to IS NULL OR a.valid_to > $4)
+           WHERE (m.id = $1 OR ($1 IS NULL AND (m.symbol = $2 OR a.id IS NOT NULL)))
+             AND l.quote = 'USD' AND l.price IS NOT NULL
+             AND l.last_updated <= $4 AND ($3::timestamptz IS NULL OR l.last_updated >= $3)
+           ORDER BY a.id IS NULL, m.rank NULLS LAST, l.last_updated DESC
+           LIMIT 1;"#,
+    )
+    .bind(id)
+    .bind(symbol)
+    .bind(since)
+    .bind(until)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns when the data of `kind` was last updated, i.e. the timestamp of the latest listing, the
+/// latest fetch of a trending feed or the latest update of a category.
+pub async fn get_last_updated(
+    pool: &PgPool,
+    kind: DataKind,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let query = match kind {
+        DataKind::Listing => "SELECT MAX(last_updated) FROM crypto_listing_latest;",
+        DataKind::Trending => "SELECT MAX(fetched_at) FROM crypto_trending;",
+        DataKind::Category => "SELECT MAX(last_updated) FROM category;",
+    };
+
+    sqlx::query_scalar(query).fetch_one(pool).await
 }
 
 pub fn get_connection_pool(config: &DbConfig) -> PgPool {
@@ -0,0 +1,118 @@
+//! Freshness policy of the served data, so that consumers can tell how old a price is and stale
+//! data is noticed when the ingestion of `coin-market-cap` stops.
+//!
+//! Each type of data has a maximum age set in the `freshness` section of the configuration. The
+//! responses carrying such data report when it was last updated through the `Age` and
+//! `X-Data-Last-Updated` headers, and flag it as `stale` in their body once it's older than its
+//! maximum age. `/health_check` reports the service as degraded in that case, with
+//! `503 Service Unavailable` by default.
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, HttpResponseBuilder};
+use chrono::{prelude::*, Duration};
+
+use crate::config::FreshnessConfig;
+use crate::model::DataFreshness;
+
+/// Header with the timestamp of the oldest data of the response (RFC 3339).
+pub const LAST_UPDATED_HEADER: &str = "X-Data-Last-Updated";
+
+/// Types of data with their own maximum age.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataKind {
+    Listing,
+    Trending,
+    Category,
+}
+
+impl DataKind {
+    pub const ALL: [DataKind; 3] = [DataKind::Listing, DataKind::Trending, DataKind::Category];
+
+    /// Name of the data type in the `freshness` section of the configuration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataKind::Listing => "listing",
+            DataKind::Trending => "trending",
+            DataKind::Category => "category",
+        }
+    }
+}
+
+/// Maximum age of each type of data.
+#[derive(Debug, Clone)]
+pub struct Freshness {
+    config: FreshnessConfig,
+}
+
+impl Freshness {
+    pub fn new(config: FreshnessConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns the maximum age of `kind`, if any.
+    pub fn max_age(&self, kind: DataKind) -> Option<u64> {
+        let max_age = &self.config.max_age;
+        match kind {
+            DataKind::Listing => max_age.listing,
+            DataKind::Trending => max_age.trending,
+            DataKind::Category => max_age.category,
+        }
+    }
+
+    /// Returns whether data of `kind` updated at `last_updated` is stale at `now`.
+    pub fn is_stale(
+        &self,
+        kind: DataKind,
+        last_updated: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        self.max_age(kind)
+            .is_some_and(|max_age| now - last_updated > Duration::seconds(max_age as i64))
+    }
+
+    /// Returns the status of the health check once any type of data is stale.
+    pub fn degraded_status(&self) -> StatusCode {
+        self.config.degraded_status
+    }
+
+    /// Returns whether the conversions priced from stale listings are refused.
+    pub fn refuse_stale_conversions(&self) -> bool {
+        self.config.refuse_stale_conversions
+    }
+
+    /// Returns the freshness of `kind` last updated at `last_updated` (if ever), unless it has no
+    /// maximum age.
+    pub fn check(
+        &self,
+        kind: DataKind,
+        last_updated: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<DataFreshness> {
+        let max_age = self.max_age(kind)?;
+
+        Some(DataFreshness {
+            data: kind.as_str(),
+            last_updated,
+            age: last_updated.map(|last_updated| (now - last_updated).num_seconds().max(0)),
+            max_age,
+            stale: last_updated.is_none_or(|last_updated| self.is_stale(kind, last_updated, now)),
+        })
+    }
+}
+
+/// Starts a successful response reporting the age of data updated at `last_updated` (i.e. the
+/// oldest data of the body), if any.
+pub fn respond(last_updated: Option<DateTime<Utc>>) -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    if let Some(last_updated) = last_updated {
+        let age = (Utc::now() - last_updated).num_seconds().max(0);
+        response
+            .insert_header((header::AGE, age.to_string()))
+            .insert_header((
+                LAST_UPDATED_HEADER,
+                last_updated.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+    }
+
+    response
+}
@@ -1,5 +1,7 @@
 pub mod cfx;
 pub mod config;
 pub mod database;
+pub mod freshness;
 pub mod model;
 pub mod route;
+pub mod upstream;
@@ -4,7 +4,16 @@ use crypto_forex::config;
 /// Entry point of the `crypto_forex` application responsible for serving information about
 /// cryptocurrencies through a Restful API consisting of the following endpoints:
 /// - `/crypto/map`
+/// - `/crypto/{id}/aliases`
 /// - `/crypto/listing`
+/// - `/crypto/category`
+/// - `/crypto/category/{id}`
+/// - `/crypto/movers`
+/// - `/crypto/trending/{feed}`
+/// - `/crypto/convert`
+/// - `/crypto/platform`
+/// - `/crypto/contract/{chain}/{address}`
+/// - `/admin/ingestion`
 
 // A more correct return type would be `Result<!, CfxError>`, but the *never* type is still
 // experimental.
@@ -1,81 +1,264 @@
 //! Module that retrieves information from the cryptocurrencies database. Fundamentally, it contains
-//! the `CryptoPlatform` struct that represents a cryptocurrency platform used by other, the
-//! `CryptoMap` struct that describes all the characteristics of a specific cryptocurrency and the
-//! `CryptoListing` struct provides accurate and timely data for the cryptoasset.
+//! the `Platform` struct that represents a blockchain on which tokens are issued, the
+//! `TokenContract` struct that locates a token on one of those chains, the `CryptoMap` struct that
+//! describes all the characteristics of a specific cryptocurrency (whose past names and symbols are
+//! kept as `CryptoAlias`) and the `CryptoListing` struct provides accurate and timely data for the
+//! cryptoasset. Besides, the `Category` struct groups cryptocurrencies that belong to the same coin
+//! category, while the `Mover` struct describes a cryptocurrency in a trending feed (e.g. top
+//! gainers and losers) and the `Conversion` struct holds the price of an amount of a cryptocurrency
+//! in another currency. Finally, the `IngestRun` struct audits a fetch of the listings, while the
+//! `Health` struct reports the `DataFreshness` of each type of data.
 
+use bigdecimal::BigDecimal;
 use chrono::prelude::*;
-use rust_decimal::prelude::*;
+use serde::Serialize;
 use sqlx::FromRow;
 
-#[derive(Debug, FromRow)]
-pub struct CryptoPlatform {
+#[derive(Debug, FromRow, Serialize)]
+pub struct Platform {
     pub id: i32,
-    pub platform: i32,
-    pub token_address: String,
+    /// Name of the chain, e.g. `Ethereum` or `Binance Smart Chain (BEP20)`.
+    pub name: String,
+    /// The CoinMarketCap's `id` of the native coin of the chain.
+    pub coin_id: Option<i32>,
+    pub symbol: Option<String>,
+    pub slug: Option<String>,
 }
 
-#[derive(Debug, FromRow)]
+/// Address of the contract of a token on a platform.
+#[derive(Debug, FromRow, Serialize)]
+pub struct TokenContract {
+    pub platform_id: i32,
+    pub platform: String,
+    pub contract_address: String,
+}
+
+/// A `CryptoMap` along with the contracts of the token on every chain on which it is deployed.
+#[derive(Debug, Serialize)]
+pub struct Token {
+    #[serde(flatten)]
+    pub crypto: CryptoMap,
+    pub contracts: Vec<TokenContract>,
+}
+
+#[derive(Debug, FromRow, Serialize)]
 pub struct CryptoMap {
     pub id: i32,
     pub name: String,
     pub symbol: String,
     pub slug: String,
-    pub rank: i32,
-    pub is_active: bool,
-    pub first_historical_data: DateTime<Utc>,
-    pub last_historical_data: DateTime<Utc>,
+    pub rank: Option<i32>,
+    pub is_active: Option<bool>,
+    pub first_historical_data: Option<DateTime<Utc>>,
+    pub last_historical_data: Option<DateTime<Utc>>,
+    /// The `Platform` on which the token was originally issued, if any.
     pub platform: Option<i32>,
+    /// Listing status, i.e. `active`, `inactive` or `untracked`.
+    pub status: Option<String>,
+    /// When the cryptocurrency was found to be no longer active.
+    pub delisted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, FromRow)]
+/// Name, symbol, slug and rank of a cryptocurrency during the period `[valid_from, valid_to)`.
+#[derive(Debug, FromRow, Serialize)]
+pub struct CryptoAlias {
+    pub name: String,
+    pub symbol: Option<String>,
+    pub slug: Option<String>,
+    pub rank: Option<i32>,
+    pub valid_from: DateTime<Utc>,
+    /// Missing while the values are still current.
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow, Serialize)]
 pub struct CryptoListing {
     /// The CoinMarketCap's `id`.
     pub id: i32,
     /// Number of market pairs across all exchanges trading each currency.
-    pub num_market_pairs: i32,
-    pub tags: Vec<String>,
+    pub num_market_pairs: Option<i32>,
     /// Approximation of the maximum amount of coins that will ever exist in the lifetime
     /// of the currency.
-    pub max_supply: Option<Decimal>,
+    pub max_supply: Option<BigDecimal>,
     /// The amount of coins that are circulating in the market and are in public hands. It is
     /// analogous to the flowing shares in the stock market.
-    pub circulating_supply: Decimal,
+    pub circulating_supply: Option<BigDecimal>,
     /// Approximate total amount of coins in existence right now (minus any coins that have been
     /// verifiably burned).
-    pub total_supply: Decimal,
+    pub total_supply: Option<BigDecimal>,
     pub platform: Option<i32>,
     /// CoinMarketCap's market cap rank as outlined in [their methodology](https://coinmarketcap.com/methodology/).
     /// Cryptocurrencies are listed by `cmc_rank` by default.
-    pub cmc_rank: i32,
+    pub cmc_rank: Option<i32>,
     pub quote: String,
 
-    /// Latest average trade price across markets.
-    pub price: Decimal,
+    /// Latest average trade price across markets. Listings without a price are never stored, while
+    /// the rest of the market data may be missing (e.g. for newly listed cryptocurrencies or in
+    /// the history backfilled from the historical quotes).
+    pub price: BigDecimal,
     /// A measure of how much of a cryptocurrency was traded in the last 24 hours.
-    pub volume_24h: Decimal,
-    pub volume_change_24h: Decimal,
+    pub volume_24h: Option<BigDecimal>,
+    pub volume_change_24h: Option<BigDecimal>,
     /// 1 hour trading price percentage change for each currency.
-    pub percent_change_1h: Decimal,
+    pub percent_change_1h: Option<BigDecimal>,
     /// 24 hour trading price percentage change for each currency.
-    pub percent_change_24h: Decimal,
+    pub percent_change_24h: Option<BigDecimal>,
     /// 7 day trading price percentage change for each currency.
-    pub percent_change_7d: Decimal,
-    pub percent_change_30d: Decimal,
-    pub percent_change_60d: Decimal,
-    pub percent_change_90d: Decimal,
+    pub percent_change_7d: Option<BigDecimal>,
+    pub percent_change_30d: Option<BigDecimal>,
+    pub percent_change_60d: Option<BigDecimal>,
+    pub percent_change_90d: Option<BigDecimal>,
     /// The total market value of a cryptocurrency's circulating supply. It is analogous to the
     /// free-float capitalization in the stock market.
     ///
     /// `Market Cap = Current Price x Circulating Supply`
     ///
     /// (see [details](https://coinmarketcap.com/methodology/))
-    pub market_cap: Decimal,
-    pub market_cap_dominance: Decimal,
+    pub market_cap: Option<BigDecimal>,
+    pub market_cap_dominance: Option<BigDecimal>,
     /// The market cap if the max supply was in circulation.
     ///
     /// Fully-diluted market cap `(FDMC) = price x max supply`. If max supply is null, `FDMC =
     /// price x total supply`. If max supply and total supply are infinite or not available,
     /// fully-diluted market cap shows `- -`.
-    pub fully_diluted_market_cap: Decimal,
+    pub fully_diluted_market_cap: Option<BigDecimal>,
+    pub last_updated: DateTime<Utc>,
+    /// Whether `last_updated` is older than the maximum age of the listings.
+    #[sqlx(default)]
+    pub stale: bool,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Number of cryptocurrencies in the category according to CoinMarketCap.
+    pub num_tokens: Option<i32>,
+    /// Aggregate market cap of the member cryptocurrencies according to their latest listing.
+    pub market_cap: Option<BigDecimal>,
+    pub last_updated: DateTime<Utc>,
+    /// Whether `last_updated` is older than the maximum age of the categories.
+    #[sqlx(default)]
+    pub stale: bool,
+}
+
+/// A `Category` along with the cryptocurrencies that belong to it.
+#[derive(Debug, Serialize)]
+pub struct CategoryMembers {
+    #[serde(flatten)]
+    pub category: Category,
+    pub coins: Vec<CryptoMap>,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct Mover {
+    /// The CoinMarketCap's `id`.
+    pub id: i32,
+    pub name: String,
+    pub symbol: String,
+    pub price: Option<BigDecimal>,
+    /// Price percentage change over the requested window.
+    pub percent_change: Option<BigDecimal>,
     pub last_updated: DateTime<Utc>,
+    /// Whether `last_updated` is older than the maximum age of the listings (or trending feeds).
+    #[sqlx(default)]
+    pub stale: bool,
+}
+
+/// Biggest gainers and losers over a window of time.
+#[derive(Debug, Serialize)]
+pub struct Movers {
+    pub window: String,
+    /// Either `upstream`, if taken from CoinMarketCap's gainers and losers feed, or `local`, if
+    /// computed from the latest listings.
+    pub source: &'static str,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+}
+
+/// A cryptocurrency identified either by its CoinMarketCap's `id` or by its symbol. Since symbols
+/// are not unique (and change with rebrands), the latter refers to the best ranked cryptocurrency
+/// with that symbol at the time of interest.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Asset {
+    Id(i32),
+    Symbol(String),
+}
+
+impl std::fmt::Display for Asset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Asset::Id(id) => write!(f, "id {}", id),
+            Asset::Symbol(symbol) => write!(f, "symbol {}", symbol),
+        }
+    }
+}
+
+/// Price in USD of a cryptocurrency according to one of its listings.
+#[derive(Debug, FromRow)]
+pub struct Price {
+    pub id: i32,
+    pub symbol: String,
+    pub price: BigDecimal,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// Price of `amount` units of a cryptocurrency in the currency `convert`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversion {
+    /// The CoinMarketCap's `id`.
+    pub id: i32,
+    pub symbol: String,
+    pub amount: BigDecimal,
+    pub convert: String,
+    pub price: BigDecimal,
+    pub last_updated: DateTime<Utc>,
+    /// Either `local`, if computed from the cached listings, or `upstream`, if the rate was taken
+    /// from CoinMarketCap's price conversion tool.
+    pub source: &'static str,
+    /// Whether the rate is older than the maximum age of the listings.
+    pub stale: bool,
+}
+
+/// Run of the ingestion of `coin-market-cap` recorded in table `ingest_run`.
+#[derive(Debug, FromRow, Serialize)]
+pub struct IngestRun {
+    pub id: i64,
+    /// Endpoint of the CoinMarketCap API and its query parameters.
+    pub endpoint: String,
+    pub params: String,
+    /// Either `running`, `succeeded` or `failed`.
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub rows_inserted: i32,
+    pub rows_updated: i32,
+    /// Listings left out of `crypto_listing` by the validation, either dropped or quarantined.
+    pub rows_rejected: i32,
+    pub rows_quarantined: i32,
+    pub credits_used: i32,
+    pub error: Option<String>,
+}
+
+/// Freshness of a type of data, i.e. how long ago it was last updated.
+#[derive(Debug, Serialize)]
+pub struct DataFreshness {
+    /// Either `listing`, `trending` or `category`.
+    pub data: &'static str,
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Seconds since `last_updated`.
+    pub age: Option<i64>,
+    pub max_age: u64,
+    /// Whether the data is older than `max_age`, or missing.
+    pub stale: bool,
+}
+
+/// Health of the service, `degraded` once any type of data is stale.
+#[derive(Debug, Serialize)]
+pub struct Health {
+    /// Either `ok` or `degraded`.
+    pub status: &'static str,
+    pub freshness: Vec<DataFreshness>,
 }
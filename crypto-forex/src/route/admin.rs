@@ -0,0 +1,75 @@
+use crate::cfx::CfxError;
+use crate::config::AdminConfig;
+use crate::database;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+/// Maximum number of ingestion runs returned.
+const MAX_LIMIT: i64 = 500;
+
+#[derive(serde::Deserialize)]
+pub struct Params {
+    endpoint: Option<String>,
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+impl Params {
+    fn status(&self) -> Result<Option<&str>, CfxError> {
+        match self.status.as_deref() {
+            None => Ok(None),
+            Some(status @ ("running" | "succeeded" | "failed")) => Ok(Some(status)),
+            Some(other) => Err(CfxError::BadRequest(format!(
+                "unsupported status `{}`, use one of running, succeeded or failed",
+                other
+            ))),
+        }
+    }
+
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// Fails unless `request` carries the token of the `admin` section of the configuration.
+fn authorize(request: &HttpRequest, admin: &AdminConfig) -> Result<(), CfxError> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(CfxError::Unauthorized)?;
+
+    // Compared in constant time, so the token can't be guessed from the response time.
+    let (expected, token) = (admin.token.as_bytes(), token.as_bytes());
+    let diff = expected
+        .iter()
+        .zip(token)
+        .fold(0, |diff, (expected, byte)| diff | (expected ^ byte));
+    if expected.is_empty() || expected.len() != token.len() || diff != 0 {
+        return Err(CfxError::Unauthorized);
+    }
+
+    Ok(())
+}
+
+/// Returns the most recent ingestion runs of `coin-market-cap` (20 by default), optionally filtered
+/// by `endpoint` (e.g. `/v1/cryptocurrency/listings/latest`) and `status`, so that stale data can
+/// be traced back to a failed or missing poll.
+pub async fn ingestion(
+    request: HttpRequest,
+    query: web::Query<Params>,
+    pool: web::Data<PgPool>,
+    admin: web::Data<AdminConfig>,
+) -> Result<HttpResponse, CfxError> {
+    authorize(&request, &admin)?;
+    let runs = database::get_ingest_runs(
+        &pool,
+        query.endpoint.as_deref(),
+        query.status()?,
+        query.limit(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(runs))
+}
@@ -0,0 +1,43 @@
+use crate::cfx::CfxError;
+use crate::database;
+use crate::freshness::{self, DataKind, Freshness};
+use crate::model::CategoryMembers;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
+
+/// Lists all the coin categories along with the aggregate market cap of their members.
+pub async fn categories(
+    pool: web::Data<PgPool>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
+    let mut categories = database::get_categories(&pool).await?;
+    let now = Utc::now();
+    for category in &mut categories {
+        category.stale = freshness.is_stale(DataKind::Category, category.last_updated, now);
+    }
+    let oldest = categories
+        .iter()
+        .map(|category| category.last_updated)
+        .min();
+
+    Ok(freshness::respond(oldest).json(categories))
+}
+
+/// Returns the coin category `id` along with the cryptocurrencies that belong to it.
+pub async fn category(
+    id: web::Path<String>,
+    pool: web::Data<PgPool>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
+    let id = id.into_inner();
+    log::info!("Category with id {}!", id);
+
+    let mut category = database::get_category(&pool, &id)
+        .await?
+        .ok_or_else(|| CfxError::NotFound(format!("Category {}", id)))?;
+    category.stale = freshness.is_stale(DataKind::Category, category.last_updated, Utc::now());
+    let coins = database::get_category_members(&pool, &id).await?;
+
+    Ok(freshness::respond(Some(category.last_updated)).json(CategoryMembers { category, coins }))
+}
@@ -0,0 +1,147 @@
+use crate::cfx::CfxError;
+use crate::database;
+use crate::freshness::{self, DataKind, Freshness};
+use crate::model::{Asset, Conversion};
+use crate::upstream::PriceConversion;
+use actix_web::{web, HttpResponse};
+use bigdecimal::{BigDecimal, One, Zero};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+/// Listings older than this (in hours) are not used to price a conversion at a given time.
+const MAX_HISTORICAL_GAP: i64 = 24;
+
+#[derive(serde::Deserialize)]
+pub struct Params {
+    id: Option<i32>,
+    symbol: Option<String>,
+    amount: Option<BigDecimal>,
+    convert: Option<String>,
+    time: Option<DateTime<Utc>>,
+}
+
+impl Params {
+    fn asset(&self) -> Result<Asset, CfxError> {
+        match (self.id, &self.symbol) {
+            (Some(id), None) => Ok(Asset::Id(id)),
+            (None, Some(symbol)) => Ok(Asset::Symbol(symbol.to_uppercase())),
+            _ => Err(CfxError::BadRequest(
+                "exactly one of `id` or `symbol` is required".to_string(),
+            )),
+        }
+    }
+
+    fn amount(&self) -> BigDecimal {
+        self.amount.clone().unwrap_or_else(BigDecimal::one)
+    }
+
+    fn convert(&self) -> String {
+        self.convert.as_deref().unwrap_or("USD").to_uppercase()
+    }
+}
+
+/// Returns the price of `amount` (1 by default) units of the cryptocurrency `id` or `symbol` in
+/// `convert` (`USD` by default), at `time` if given. The conversion is computed from the cached
+/// listings whenever possible (i.e. into USD or another cached cryptocurrency), otherwise it falls
+/// back to CoinMarketCap's price conversion tool, if configured.
+///
+/// The rate is flagged as `stale` once older than the maximum age of the listings at `time` (or
+/// now). If stale conversions are refused, the upstream tool is tried instead of stale listings,
+/// and the conversion fails if its rate is stale too.
+pub async fn convert(
+    query: web::Query<Params>,
+    pool: web::Data<PgPool>,
+    upstream: web::Data<Option<PriceConversion>>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
+    let (asset, amount, convert) = (query.asset()?, query.amount(), query.convert());
+    let at = query.time.unwrap_or_else(Utc::now);
+    let stale = |conversion: &Conversion| {
+        freshness.is_stale(DataKind::Listing, conversion.last_updated, at)
+    };
+
+    let mut refused = None;
+    if let Some(mut conversion) =
+        local_conversion(&pool, &asset, &amount, &convert, query.time).await?
+    {
+        conversion.stale = stale(&conversion);
+        if !conversion.stale || !freshness.refuse_stale_conversions() {
+            return Ok(respond(conversion));
+        }
+        refused = Some(conversion);
+    }
+
+    match upstream.get_ref() {
+        Some(upstream) => {
+            let mut conversion = upstream
+                .convert(&asset, amount, &convert, query.time)
+                .await?;
+            conversion.stale = stale(&conversion);
+            if conversion.stale && freshness.refuse_stale_conversions() {
+                return Err(stale_error(&conversion));
+            }
+            Ok(respond(conversion))
+        }
+        None => match refused {
+            Some(conversion) => Err(stale_error(&conversion)),
+            None => Err(CfxError::NotFound(format!(
+                "Price of {} in {}",
+                asset, convert
+            ))),
+        },
+    }
+}
+
+fn respond(conversion: Conversion) -> HttpResponse {
+    freshness::respond(Some(conversion.last_updated)).json(conversion)
+}
+
+fn stale_error(conversion: &Conversion) -> CfxError {
+    CfxError::Stale(format!(
+        "Price of {} in {} (last updated at {})",
+        conversion.symbol, conversion.convert, conversion.last_updated
+    ))
+}
+
+/// Converts `amount` units of `asset` into `convert` using the cached USD prices, if both are
+/// available.
+async fn local_conversion(
+    pool: &PgPool,
+    asset: &Asset,
+    amount: &BigDecimal,
+    convert: &str,
+    time: Option<DateTime<Utc>>,
+) -> Result<Option<Conversion>, CfxError> {
+    let (since, until) = match time {
+        Some(time) => (Some(time - Duration::hours(MAX_HISTORICAL_GAP)), time),
+        None => (None, Utc::now()),
+    };
+
+    let price = match database::get_crypto_price(pool, asset, since, until).await? {
+        Some(price) => price,
+        None => return Ok(None),
+    };
+    let (rate, last_updated) = if convert == "USD" {
+        (price.price, price.last_updated)
+    } else {
+        let target = Asset::Symbol(convert.to_string());
+        match database::get_crypto_price(pool, &target, since, until).await? {
+            Some(target) if !target.price.is_zero() => (
+                &price.price / &target.price,
+                price.last_updated.min(target.last_updated),
+            ),
+            _ => return Ok(None),
+        }
+    };
+
+    Ok(Some(Conversion {
+        id: price.id,
+        symbol: price.symbol,
+        amount: amount.clone(),
+        convert: convert.to_string(),
+        price: rate * amount,
+        last_updated,
+        source: "local",
+        stale: false,
+    }))
+}
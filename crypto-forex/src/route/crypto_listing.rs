@@ -1,16 +1,31 @@
+use crate::cfx::CfxError;
 use crate::database;
-use actix_web::{web, Responder};
+use crate::freshness::{self, DataKind, Freshness};
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
 use sqlx::PgPool;
 
 #[derive(serde::Deserialize)]
 pub struct Params {
-    cmc_rank: usize,
+    cmc_rank: i32,
 }
 
-pub async fn listing(query: web::Query<Params>, pool: web::Data<PgPool>) -> impl Responder {
+/// Returns the latest listing of the cryptocurrency ranked `cmc_rank`.
+pub async fn listing(
+    query: web::Query<Params>,
+    pool: web::Data<PgPool>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
     let rank = query.cmc_rank;
     log::info!("Crypto with rank {}!", rank);
+    if rank < 1 {
+        return Err(CfxError::BadRequest("ranks start at 1".to_string()));
+    }
 
-    let listing = database::get_crypto_listing(&pool).await.unwrap();
-    format!("Crypto with rank {}:\n\n{:#?}", rank, listing[rank - 1])
+    let mut listing = database::get_crypto_listing_by_rank(&pool, rank)
+        .await?
+        .ok_or_else(|| CfxError::NotFound(format!("Crypto with rank {}", rank)))?;
+    listing.stale = freshness.is_stale(DataKind::Listing, listing.last_updated, Utc::now());
+
+    Ok(freshness::respond(Some(listing.last_updated)).json(listing))
 }
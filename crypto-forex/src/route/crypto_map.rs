@@ -1,5 +1,6 @@
+use crate::cfx::CfxError;
 use crate::database;
-use actix_web::{web, Responder};
+use actix_web::{web, HttpResponse, Responder};
 use sqlx::PgPool;
 
 #[derive(serde::Deserialize)]
@@ -14,3 +15,20 @@ pub async fn map(query: web::Query<Params>, pool: web::Data<PgPool>) -> impl Res
     let map = database::get_crypto_map(&pool).await.unwrap();
     format!("Crypto with id {}:\n\n{:#?}", id, map[id - 1])
 }
+
+/// Returns the history of the names, symbols, slugs and ranks of the cryptocurrency `id`, so that
+/// old tickers can still be resolved to the right asset.
+pub async fn aliases(
+    id: web::Path<i32>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, CfxError> {
+    let id = id.into_inner();
+    log::info!("Aliases of crypto with id {}!", id);
+
+    let aliases = database::get_crypto_aliases(&pool, id).await?;
+    if aliases.is_empty() {
+        return Err(CfxError::NotFound(format!("Crypto {}", id)));
+    }
+
+    Ok(HttpResponse::Ok().json(aliases))
+}
@@ -0,0 +1,28 @@
+use crate::cfx::CfxError;
+use crate::database;
+use crate::model::Token;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+/// Lists all the platforms, i.e. the chains on which tokens are issued.
+pub async fn platforms(pool: web::Data<PgPool>) -> Result<HttpResponse, CfxError> {
+    let platforms = database::get_platforms(&pool).await?;
+    Ok(HttpResponse::Ok().json(platforms))
+}
+
+/// Returns the token deployed at `address` on `chain` (the id, name or slug of a platform) along
+/// with its contracts on every other chain.
+pub async fn contract(
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, CfxError> {
+    let (chain, address) = path.into_inner();
+    log::info!("Token at {} on {}!", address, chain);
+
+    let crypto = database::get_token_by_contract(&pool, &chain, &address)
+        .await?
+        .ok_or_else(|| CfxError::NotFound(format!("Token at {} on {}", address, chain)))?;
+    let contracts = database::get_token_contracts(&pool, crypto.id).await?;
+
+    Ok(HttpResponse::Ok().json(Token { crypto, contracts }))
+}
@@ -0,0 +1,125 @@
+use crate::cfx::CfxError;
+use crate::database;
+use crate::freshness::{self, DataKind, Freshness};
+use crate::model::{Mover, Movers};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+/// Snapshots of the trending feeds older than this (in minutes) are considered outdated.
+const MAX_SNAPSHOT_AGE: i64 = 60;
+/// Maximum number of cryptocurrencies returned per list.
+const MAX_LIMIT: i64 = 100;
+
+#[derive(serde::Deserialize)]
+pub struct Params {
+    window: Option<String>,
+    limit: Option<i64>,
+}
+
+impl Params {
+    fn window(&self) -> &str {
+        self.window.as_deref().unwrap_or("24h")
+    }
+
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(10).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// Returns the column of `crypto_listing` with the percent change over `window`.
+fn percent_change_column(window: &str) -> Result<&'static str, CfxError> {
+    match window {
+        "1h" => Ok("percent_change_1h"),
+        "24h" => Ok("percent_change_24h"),
+        "7d" => Ok("percent_change_7d"),
+        "30d" => Ok("percent_change_30d"),
+        "60d" => Ok("percent_change_60d"),
+        "90d" => Ok("percent_change_90d"),
+        other => Err(CfxError::BadRequest(format!(
+            "unsupported window `{}`, use one of 1h, 24h, 7d, 30d, 60d or 90d",
+            other
+        ))),
+    }
+}
+
+/// Flags the `movers` older than the maximum age of `kind`, and returns the oldest update among
+/// them.
+fn flag_stale(
+    movers: &mut [Mover],
+    kind: DataKind,
+    freshness: &Freshness,
+) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+    for mover in movers.iter_mut() {
+        mover.stale = freshness.is_stale(kind, mover.last_updated, now);
+    }
+
+    movers.iter().map(|mover| mover.last_updated).min()
+}
+
+/// Returns the biggest gainers and losers over `window` (`24h` by default). These are taken from
+/// CoinMarketCap's gainers and losers feed if a recent snapshot is available, otherwise they are
+/// computed from the latest listings.
+pub async fn movers(
+    query: web::Query<Params>,
+    pool: web::Data<PgPool>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
+    let (window, limit) = (query.window(), query.limit());
+    let column = percent_change_column(window)?;
+    let since = Utc::now() - Duration::minutes(MAX_SNAPSHOT_AGE);
+
+    let gainers = database::get_crypto_trending(&pool, "gainers", window, since, limit).await?;
+    let losers = database::get_crypto_trending(&pool, "losers", window, since, limit).await?;
+
+    let mut movers = if !gainers.is_empty() && !losers.is_empty() {
+        Movers {
+            window: window.to_string(),
+            source: "upstream",
+            gainers,
+            losers,
+        }
+    } else {
+        Movers {
+            window: window.to_string(),
+            source: "local",
+            gainers: database::get_crypto_movers(&pool, column, true, limit).await?,
+            losers: database::get_crypto_movers(&pool, column, false, limit).await?,
+        }
+    };
+    let kind = match movers.source {
+        "upstream" => DataKind::Trending,
+        _ => DataKind::Listing,
+    };
+    let oldest = [
+        flag_stale(&mut movers.gainers, kind, &freshness),
+        flag_stale(&mut movers.losers, kind, &freshness),
+    ]
+    .into_iter()
+    .flatten()
+    .min();
+
+    Ok(freshness::respond(oldest).json(movers))
+}
+
+/// Returns the latest snapshot of the trending `feed` (i.e. `gainers`, `losers`, `latest` or
+/// `most_visited`) over `window` (`24h` by default).
+pub async fn trending(
+    feed: web::Path<String>,
+    query: web::Query<Params>,
+    pool: web::Data<PgPool>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
+    let feed = feed.into_inner();
+    if !["gainers", "losers", "latest", "most_visited"].contains(&feed.as_str()) {
+        return Err(CfxError::NotFound(format!("Trending feed {}", feed)));
+    }
+
+    let since = Utc::now() - Duration::minutes(MAX_SNAPSHOT_AGE);
+    let mut trending =
+        database::get_crypto_trending(&pool, &feed, query.window(), since, query.limit()).await?;
+    let oldest = flag_stale(&mut trending, DataKind::Trending, &freshness);
+
+    Ok(freshness::respond(oldest).json(trending))
+}
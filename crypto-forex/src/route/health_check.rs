@@ -1,5 +1,41 @@
-use actix_web::HttpResponse;
+use crate::cfx::CfxError;
+use crate::database;
+use crate::freshness::{DataKind, Freshness};
+use crate::model::Health;
+use actix_web::{http::StatusCode, web, HttpResponse};
+use chrono::Utc;
+use sqlx::PgPool;
 
-pub async fn health_check() -> HttpResponse {
-    HttpResponse::Ok().finish()
+/// Reports the service as `degraded` once any type of data with a maximum age (see the `freshness`
+/// section of the configuration) is stale or missing, e.g. because the ingestion stopped. It
+/// answers with the `degraded_status` of the configuration then (`503 Service Unavailable` by
+/// default), so that load balancers and probes notice it.
+pub async fn health_check(
+    pool: web::Data<PgPool>,
+    freshness: web::Data<Freshness>,
+) -> Result<HttpResponse, CfxError> {
+    let now = Utc::now();
+    let mut checks = Vec::new();
+    for kind in DataKind::ALL {
+        if freshness.max_age(kind).is_some() {
+            let last_updated = database::get_last_updated(&pool, kind).await?;
+            checks.extend(freshness.check(kind, last_updated, now));
+        }
+    }
+
+    let degraded = checks.iter().any(|check| check.stale);
+    if degraded {
+        log::warn!("Stale data: {:?}", checks);
+    }
+
+    let status = if degraded {
+        freshness.degraded_status()
+    } else {
+        StatusCode::OK
+    };
+
+    Ok(HttpResponse::build(status).json(Health {
+        status: if degraded { "degraded" } else { "ok" },
+        freshness: checks,
+    }))
 }
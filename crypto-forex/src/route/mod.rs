@@ -1,3 +1,8 @@
+pub mod admin;
+pub mod crypto_category;
+pub mod crypto_convert;
 pub mod crypto_listing;
 pub mod crypto_map;
+pub mod crypto_platform;
+pub mod crypto_trending;
 pub mod health_check;
@@ -0,0 +1,177 @@
+//! Client of CoinMarketCap's price conversion tool (i.e. the endpoint `/v2/tools/price-conversion`),
+//! used as a fallback for the conversions that can't be priced from the local cache. The rates are
+//! cached in memory for `cache_ttl` seconds, so repeated conversions don't consume call credits.
+
+use bigdecimal::{BigDecimal, One};
+use chrono::prelude::*;
+use cmc_common::de::lenient_number;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::cfx::CfxError;
+use crate::config::UpstreamConfig;
+use crate::model::{Asset, Conversion};
+
+const ENDPOINT: &str = "/v2/tools/price-conversion";
+
+/// The endpoint returns a single object when queried by `id`, but a list when queried by `symbol`.
+/// The data is matched by hand rather than through an untagged enum, whose buffering loses the
+/// numbers parsed with arbitrary precision.
+#[derive(Debug, Deserialize)]
+struct Response {
+    data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Data {
+    id: i32,
+    symbol: String,
+    quote: HashMap<String, Quote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Quote {
+    #[serde(default, deserialize_with = "lenient_number")]
+    price: Option<BigDecimal>,
+    last_updated: DateTime<Utc>,
+}
+
+/// Body returned by the server when a request fails.
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    status: ErrorStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorStatus {
+    error_code: u32,
+    error_message: Option<String>,
+}
+
+/// Key of the cached rates, i.e. the price of one unit of an asset in `convert` at `time`.
+type CacheKey = (Asset, String, Option<DateTime<Utc>>);
+
+pub struct PriceConversion {
+    config: UpstreamConfig,
+    client: reqwest::Client,
+    cache: Mutex<HashMap<CacheKey, (Instant, Conversion)>>,
+}
+
+impl PriceConversion {
+    pub fn new(config: UpstreamConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the price of `amount` units of `asset` in `convert`, either at `time` or the latest
+    /// one.
+    pub async fn convert(
+        &self,
+        asset: &Asset,
+        amount: BigDecimal,
+        convert: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> Result<Conversion, CfxError> {
+        let key = (asset.clone(), convert.to_string(), time);
+        let rate = match self.cached(&key) {
+            Some(rate) => rate,
+            None => {
+                let rate = self.request(asset, convert, time).await?;
+                self.cache(key, rate.clone());
+                rate
+            }
+        };
+
+        Ok(Conversion {
+            price: &rate.price * &amount,
+            amount,
+            ..rate
+        })
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.cache_ttl)
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<Conversion> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl())
+            .map(|(_, rate)| rate.clone())
+    }
+
+    fn cache(&self, key: CacheKey, rate: Conversion) {
+        let ttl = self.ttl();
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+        cache.insert(key, (Instant::now(), rate));
+    }
+
+    /// Requests the price of one unit of `asset` in `convert` to the price conversion tool.
+    async fn request(
+        &self,
+        asset: &Asset,
+        convert: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> Result<Conversion, CfxError> {
+        let mut params = vec![
+            ("amount", "1".to_string()),
+            ("convert", convert.to_string()),
+        ];
+        match asset {
+            Asset::Id(id) => params.push(("id", id.to_string())),
+            Asset::Symbol(symbol) => params.push(("symbol", symbol.clone())),
+        }
+        if let Some(time) = time {
+            params.push(("time", time.to_rfc3339()));
+        }
+
+        let response = self
+            .client
+            .get(self.config.base_url.clone() + ENDPOINT)
+            .header("X-CMC_PRO_API_KEY", &self.config.api_key)
+            .query(&params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error: ErrorResponse = response.json().await?;
+            return Err(CfxError::Upstream(format!(
+                "error {}: {}",
+                error.status.error_code,
+                error.status.error_message.unwrap_or_default()
+            )));
+        }
+
+        let data = match response.json::<Response>().await?.data {
+            // Symbols are not unique, but the best ranked cryptocurrency comes first.
+            Value::Array(data) => data.into_iter().next(),
+            data => Some(data),
+        };
+        let data = data
+            .map(serde_json::from_value::<Data>)
+            .transpose()
+            .map_err(|error| CfxError::Upstream(format!("invalid response: {}", error)))?;
+        let not_found = || CfxError::NotFound(format!("Price of {} in {}", asset, convert));
+        let mut data = data.ok_or_else(not_found)?;
+        let quote = data.quote.remove(convert).ok_or_else(not_found)?;
+
+        Ok(Conversion {
+            id: data.id,
+            symbol: data.symbol,
+            amount: BigDecimal::one(),
+            convert: convert.to_string(),
+            price: quote.price.ok_or_else(not_found)?,
+            last_updated: quote.last_updated,
+            source: "upstream",
+            stale: false,
+        })
+    }
+}
@@ -0,0 +1,123 @@
+//! Helpers shared by the integration tests, which serve the routes against the database of the
+//! development configuration, seeded with the test data of `coin-market-cap`.
+// Each test binary only uses some of them.
+#![allow(dead_code)]
+
+use chrono::Utc;
+use coin_market_cap::coin_market::{categories, category, info, listing, map, trending};
+use coin_market_cap::database::{
+    clear_all_tables, update_categories, update_category_members, update_crypto_listing,
+    update_crypto_map, update_crypto_trending, update_token_contracts,
+};
+use crypto_forex::cfx::CfxServer;
+use crypto_forex::config::{self, Configuration, MaxAgeConfig};
+use crypto_forex::database::get_connection_pool;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// Returns the development configuration on a random port, where the data is never stale.
+pub fn test_config() -> Configuration {
+    let mut config = config::load_config().expect("Error loading the configuration!");
+    config.application.port = 0;
+    config.freshness.max_age = MaxAgeConfig {
+        listing: None,
+        trending: None,
+        category: None,
+    };
+
+    config
+}
+
+/// Spawns the server with `config`, returning its base URL.
+pub async fn spawn_server(config: Configuration) -> String {
+    let server = CfxServer::build(config)
+        .await
+        .expect("Failed to build the server");
+    let port = server.port();
+    actix_rt::spawn(async move {
+        server.run().await.expect("Failed to run the server");
+    });
+
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// Empties the database, then stores the map of 50 cryptocurrencies and the listings of BTC, ETH,
+/// BNB and ADA (last updated between 2021-10-12T17:36:08Z and 2021-10-12T17:37:02Z).
+pub async fn seed_database(config: &Configuration) -> PgPool {
+    let map_json = include_str!("../../../coin-market-cap/tests/data/cryptocurrency_map_50.json");
+    let listing_json =
+        include_str!("../../../coin-market-cap/tests/data/cryptocurrency_listings_latest_4.json");
+    let response_map: map::Response =
+        serde_json::from_str(map_json).expect("Failed to parse input!");
+    let response_listing: listing::Response =
+        serde_json::from_str(listing_json).expect("Failed to parse input!");
+
+    let pool = get_connection_pool(&config.database);
+    clear_all_tables(pool.clone())
+        .await
+        .expect("Failed to clear the database");
+    update_crypto_map(response_map, pool.clone())
+        .await
+        .expect("Failed to store the map");
+    update_crypto_listing(response_listing, pool.clone())
+        .await
+        .expect("Failed to store the listings");
+
+    pool
+}
+
+/// Stores 3 categories (Smart Contracts, Store of Value and Stablecoin), along with the 4 members
+/// of Smart Contracts (ETH, ADA, LINK and AVAX), once the database is seeded.
+pub async fn seed_categories(pool: &PgPool) {
+    let categories_json =
+        include_str!("../../../coin-market-cap/tests/data/cryptocurrency_categories_3.json");
+    let category_json =
+        include_str!("../../../coin-market-cap/tests/data/cryptocurrency_category_4.json");
+    let response_categories: categories::Response =
+        serde_json::from_str(categories_json).expect("Failed to parse input!");
+    let response_category: category::Response =
+        serde_json::from_str(category_json).expect("Failed to parse input!");
+
+    update_categories(&response_categories, pool.clone())
+        .await
+        .expect("Failed to store the categories");
+    update_category_members(&response_category, pool.clone())
+        .await
+        .expect("Failed to store the members");
+}
+
+/// Stores a snapshot of the trending `feed` over 24h, fetched now, once the database is seeded.
+pub async fn seed_trending(pool: &PgPool, feed: &str) {
+    let trending_json = include_str!(
+        "../../../coin-market-cap/tests/data/cryptocurrency_trending_gainers_losers_5.json"
+    );
+    let response_trending: trending::Response =
+        serde_json::from_str(trending_json).expect("Failed to parse input!");
+
+    update_crypto_trending(&response_trending, feed, "24h", Utc::now(), pool.clone())
+        .await
+        .expect("Failed to store the trending feed");
+}
+
+/// Stores the contracts of USDT (on Ethereum, Tron, BEP20 and Solana) and BUSD (on BEP2, Ethereum
+/// and BEP20), once the database is seeded.
+pub async fn seed_token_contracts(pool: &PgPool) {
+    let info_json = include_str!("../../../coin-market-cap/tests/data/cryptocurrency_info_3.json");
+    let response_info: info::Response =
+        serde_json::from_str(info_json).expect("Failed to parse input!");
+
+    update_token_contracts(&response_info, pool.clone())
+        .await
+        .expect("Failed to store the contracts");
+}
+
+/// Sends a `GET` request to `url`, returning the status, the headers and the JSON body (`null` if
+/// the body is not JSON).
+pub async fn get(url: &str) -> (u16, HeaderMap, Value) {
+    let response = reqwest::get(url).await.expect("Failed to send the request");
+    let (status, headers) = (response.status().as_u16(), response.headers().clone());
+    let body = response.json().await.unwrap_or(Value::Null);
+
+    (status, headers, body)
+}
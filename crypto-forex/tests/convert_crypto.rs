@@ -0,0 +1,154 @@
+mod common;
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use bigdecimal::BigDecimal;
+use common::{get, seed_database, spawn_server, test_config};
+use crypto_forex::config::UpstreamConfig;
+use serde_json::Value;
+use serial_test::serial;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Answers like CoinMarketCap's price conversion tool, which only knows Tether (`id=825`).
+async fn price_conversion(
+    query: web::Query<HashMap<String, String>>,
+    requests: web::Data<Arc<AtomicUsize>>,
+) -> HttpResponse {
+    requests.fetch_add(1, Ordering::SeqCst);
+    if query.get("id").map(String::as_str) == Some("825") {
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .body(include_str!("data/tools_price_conversion_825.json"))
+    } else {
+        HttpResponse::BadRequest()
+            .content_type("application/json")
+            .body(include_str!("data/tools_price_conversion_error.json"))
+    }
+}
+
+/// Spawns a mock of the price conversion tool, returning its base URL and the number of requests
+/// it received.
+fn spawn_upstream() -> (String, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let data = web::Data::new(requests.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind a random port");
+    let port = listener.local_addr().unwrap().port();
+    let server = HttpServer::new(move || {
+        App::new()
+            .route(
+                "/v2/tools/price-conversion",
+                web::get().to(price_conversion),
+            )
+            .app_data(data.clone())
+    })
+    .listen(listener)
+    .expect("Failed to listen")
+    .run();
+    actix_rt::spawn(async move {
+        server.await.expect("Failed to run the mock");
+    });
+
+    (format!("http://127.0.0.1:{}", port), requests)
+}
+
+/// Spawns the server over the seeded database, falling back to the mock of the price conversion
+/// tool if `upstream`. Returns the base URL of the server and the number of upstream requests.
+async fn spawn(upstream: bool) -> (String, Arc<AtomicUsize>) {
+    let mut config = test_config();
+    seed_database(&config).await;
+    let (upstream_url, requests) = spawn_upstream();
+    if upstream {
+        config.upstream = Some(UpstreamConfig {
+            base_url: upstream_url,
+            api_key: "test-key".to_string(),
+            cache_ttl: 60,
+        });
+    }
+
+    (spawn_server(config).await, requests)
+}
+
+/// Returns the decimal serialized as a string in `value`.
+fn decimal(value: &Value) -> BigDecimal {
+    BigDecimal::from_str(value.as_str().expect("Not a decimal!")).unwrap()
+}
+
+fn price(s: &str) -> BigDecimal {
+    BigDecimal::from_str(s).unwrap()
+}
+
+#[actix_rt::test]
+#[serial]
+async fn convert_from_local_listings() {
+    let (base_url, requests) = spawn(true).await;
+
+    let url = format!("{}/crypto/convert?symbol=btc&amount=2", base_url);
+    let (status, _, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["source"], "local");
+    assert_eq!(body["symbol"], "BTC");
+    assert_eq!(decimal(&body["price"]), price("111368.88019135852"));
+
+    // Into another cryptocurrency of the listings, i.e. ETH per BTC.
+    let url = format!("{}/crypto/convert?id=1&convert=eth", base_url);
+    let (status, _, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["source"], "local");
+    let eth_per_btc = decimal(&body["price"]);
+    assert!(eth_per_btc > price("15.9") && eth_per_btc < price("16"));
+
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+}
+
+/// Tether is in the map but not in the listings.
+#[actix_rt::test]
+#[serial]
+async fn convert_from_upstream() {
+    let (base_url, requests) = spawn(true).await;
+
+    let url = format!("{}/crypto/convert?id=825&amount=100", base_url);
+    let (status, _, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["source"], "upstream");
+    assert_eq!(body["symbol"], "USDT");
+    assert_eq!(decimal(&body["price"]), price("100.0123456789"));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn convert_from_upstream_cache() {
+    let (base_url, requests) = spawn(true).await;
+
+    for amount in ["1", "3"] {
+        let url = format!("{}/crypto/convert?id=825&amount={}", base_url, amount);
+        let (status, _, body) = get(&url).await;
+        assert_eq!(status, 200);
+        assert_eq!(
+            decimal(&body["price"]),
+            price("1.000123456789") * price(amount)
+        );
+    }
+    // The rate is cached, so only the first conversion reached the price conversion tool.
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn convert_upstream_error() {
+    let (base_url, requests) = spawn(true).await;
+
+    let (status, _, body) = get(&format!("{}/crypto/convert?symbol=XYZ", base_url)).await;
+    assert_eq!(status, 502);
+    assert_eq!(body, Value::Null);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // Without the price conversion tool, the conversion is simply not found.
+    let (base_url, requests) = spawn(false).await;
+    let (status, _, _) = get(&format!("{}/crypto/convert?symbol=XYZ", base_url)).await;
+    assert_eq!(status, 404);
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{get, seed_categories, seed_database, spawn_server, test_config};
+use rust_decimal::Decimal;
+use serial_test::serial;
+use std::str::FromStr;
+
+#[actix_rt::test]
+#[serial]
+async fn serve_categories() {
+    let config = test_config();
+    let pool = seed_database(&config).await;
+    seed_categories(&pool).await;
+    let base_url = spawn_server(config).await;
+
+    let (status, _, body) = get(&format!("{}/crypto/category/", base_url)).await;
+    assert_eq!(status, 200);
+    let categories = body.as_array().expect("Not a list of categories!");
+    assert_eq!(categories.len(), 3);
+    // Only ETH and ADA have a listing among the members of Smart Contracts.
+    assert_eq!(categories[0]["name"], "Smart Contracts");
+    let market_cap = categories[0]["market_cap"]
+        .as_str()
+        .expect("Not a decimal!");
+    assert_eq!(
+        Decimal::from_str(market_cap).unwrap(),
+        Decimal::from_str("481410947800.71774").unwrap()
+    );
+    assert!(categories[1]["market_cap"].is_null());
+
+    let url = format!("{}/crypto/category/6051a82566fc1b42617d6dc6", base_url);
+    let (status, _, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["title"], "Smart Contracts");
+    // Ordered by rank.
+    let coins: Vec<&str> = body["coins"]
+        .as_array()
+        .expect("Not a list of coins!")
+        .iter()
+        .map(|coin| coin["symbol"].as_str().unwrap())
+        .collect();
+    assert_eq!(coins, ["ETH", "ADA", "LINK", "AVAX"]);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_missing_category() {
+    let config = test_config();
+    let pool = seed_database(&config).await;
+    seed_categories(&pool).await;
+    let base_url = spawn_server(config).await;
+
+    let (status, _, _) = get(&format!("{}/crypto/category/unknown", base_url)).await;
+    assert_eq!(status, 404);
+}
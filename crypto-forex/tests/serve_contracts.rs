@@ -0,0 +1,58 @@
+mod common;
+
+use common::{get, seed_database, seed_token_contracts, spawn_server, test_config};
+use serial_test::serial;
+
+#[actix_rt::test]
+#[serial]
+async fn serve_contract() {
+    let config = test_config();
+    let pool = seed_database(&config).await;
+    seed_token_contracts(&pool).await;
+    let base_url = spawn_server(config).await;
+
+    // Contract addresses are case insensitive.
+    let url = format!(
+        "{}/crypto/contract/ethereum/0xDAC17F958D2EE523A2206206994597C13D831EC7",
+        base_url
+    );
+    let (status, _, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["id"], 825);
+    assert_eq!(body["symbol"], "USDT");
+    let contracts = body["contracts"]
+        .as_array()
+        .expect("Not a list of contracts!");
+    assert_eq!(contracts.len(), 4);
+    assert!(contracts.iter().any(|contract| {
+        contract["platform"] == "Solana"
+            && contract["contract_address"] == "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"
+    }));
+
+    // The chain may also be the name of the platform.
+    let url = format!("{}/crypto/contract/Binance Chain (BEP2)/BUSD-BD1", base_url);
+    let (status, _, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["symbol"], "BUSD");
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_missing_contract() {
+    let config = test_config();
+    let pool = seed_database(&config).await;
+    seed_token_contracts(&pool).await;
+    let base_url = spawn_server(config).await;
+
+    // USDT on Tron, but not on Ethereum.
+    let url = format!(
+        "{}/crypto/contract/ethereum/TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+        base_url
+    );
+    let (status, _, _) = get(&url).await;
+    assert_eq!(status, 404);
+
+    let url = format!("{}/crypto/contract/unknown/BUSD-BD1", base_url);
+    let (status, _, _) = get(&url).await;
+    assert_eq!(status, 404);
+}
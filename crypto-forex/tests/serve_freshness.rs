@@ -0,0 +1,140 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use common::{get, seed_database, spawn_server, test_config};
+use crypto_forex::config::Configuration;
+use serial_test::serial;
+
+/// Latest update time of the seeded listings, i.e. of BTC and ETH.
+const LAST_UPDATED: &str = "2021-10-12T17:37:02Z";
+
+/// Returns the test configuration where the listings (seeded back in 2021) are stale after a
+/// minute, refusing the conversions priced from them if `refuse_stale_conversions`.
+fn stale_config(refuse_stale_conversions: bool) -> Configuration {
+    let mut config = test_config();
+    config.freshness.max_age.listing = Some(60);
+    config.freshness.refuse_stale_conversions = refuse_stale_conversions;
+
+    config
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_listing() {
+    let config = stale_config(false);
+    seed_database(&config).await;
+    let base_url = spawn_server(config).await;
+
+    let (status, headers, body) = get(&format!("{}/crypto/listing/?cmc_rank=2", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["X-Data-Last-Updated"], LAST_UPDATED);
+    let age: i64 = headers["Age"].to_str().unwrap().parse().unwrap();
+    assert!(age > 365 * 24 * 3600);
+    // ETH.
+    assert_eq!(body["id"], 1027);
+    assert_eq!(body["cmc_rank"], 2);
+    assert_eq!(body["last_updated"], LAST_UPDATED);
+    assert_eq!(body["stale"], true);
+
+    let (status, _, _) = get(&format!("{}/crypto/listing/?cmc_rank=0", base_url)).await;
+    assert_eq!(status, 400);
+    // Only 4 cryptocurrencies are listed.
+    let (status, _, _) = get(&format!("{}/crypto/listing/?cmc_rank=5", base_url)).await;
+    assert_eq!(status, 404);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_listing_rank_gap() {
+    let config = test_config();
+    let pool = seed_database(&config).await;
+    // BNB, ranked 3rd, is no longer listed.
+    sqlx::query("DELETE FROM crypto_listing_latest WHERE id = 1839;")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let base_url = spawn_server(config).await;
+
+    let (status, _, _) = get(&format!("{}/crypto/listing/?cmc_rank=3", base_url)).await;
+    assert_eq!(status, 404);
+    let (status, _, body) = get(&format!("{}/crypto/listing/?cmc_rank=4", base_url)).await;
+    assert_eq!(status, 200);
+    // ADA.
+    assert_eq!(body["id"], 2010);
+    assert_eq!(body["stale"], false);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_health_check() {
+    let config = test_config();
+    seed_database(&config).await;
+    let base_url = spawn_server(config).await;
+
+    // Without any maximum age, nothing is ever stale.
+    let (status, _, body) = get(&format!("{}/health_check", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["freshness"].as_array().unwrap().len(), 0);
+
+    let (status, _, body) = get(&format!("{}/crypto/movers", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["gainers"][0]["stale"], false);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_health_check_degraded() {
+    let mut config = stale_config(false);
+    config.freshness.max_age.trending = Some(60);
+    seed_database(&config).await;
+    let base_url = spawn_server(config).await;
+
+    let (status, _, body) = get(&format!("{}/health_check", base_url)).await;
+    assert_eq!(status, 503);
+    assert_eq!(body["status"], "degraded");
+    let checks = body["freshness"].as_array().unwrap();
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0]["data"], "listing");
+    assert_eq!(checks[0]["last_updated"], LAST_UPDATED);
+    assert_eq!(checks[0]["stale"], true);
+    // No trending feed was ever fetched.
+    assert_eq!(checks[1]["data"], "trending");
+    assert!(checks[1]["last_updated"].is_null());
+    assert_eq!(checks[1]["stale"], true);
+
+    let mut config = stale_config(false);
+    config.freshness.degraded_status = StatusCode::OK;
+    let base_url_ok = spawn_server(config).await;
+    let (status, _, body) = get(&format!("{}/health_check", base_url_ok)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "degraded");
+
+    let (status, headers, body) = get(&format!("{}/crypto/movers", base_url)).await;
+    assert_eq!(status, 200);
+    // The oldest cryptocurrency of the response, i.e. BNB.
+    assert_eq!(headers["X-Data-Last-Updated"], "2021-10-12T17:36:08Z");
+    assert_eq!(body["source"], "local");
+    assert_eq!(body["gainers"][0]["stale"], true);
+    assert_eq!(body["losers"][0]["stale"], true);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn convert_stale_listings() {
+    let config = stale_config(false);
+    seed_database(&config).await;
+    let base_url = spawn_server(config).await;
+
+    let url = format!("{}/crypto/convert?symbol=BTC", base_url);
+    let (status, headers, body) = get(&url).await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["X-Data-Last-Updated"], LAST_UPDATED);
+    assert_eq!(body["stale"], true);
+
+    // Without the price conversion tool to price it instead, the conversion is refused.
+    let config = stale_config(true);
+    let base_url = spawn_server(config).await;
+    let (status, _, _) = get(&format!("{}/crypto/convert?symbol=BTC", base_url)).await;
+    assert_eq!(status, 503);
+}
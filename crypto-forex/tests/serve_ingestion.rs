@@ -0,0 +1,105 @@
+mod common;
+
+use coin_market_cap::database::{finish_ingest_run, start_ingest_run, IngestProgress};
+use common::{get, seed_database, spawn_server, test_config};
+use crypto_forex::config::AdminConfig;
+use reqwest::header::AUTHORIZATION;
+use serde_json::Value;
+use serial_test::serial;
+
+const TOKEN: &str = "test-token";
+
+/// Spawns the server with the admin endpoints (unless `admin` is false), after recording a
+/// succeeded run of the listings and a failed run of the map. Returns the base URL of the server.
+async fn spawn(admin: bool) -> String {
+    let mut config = test_config();
+    let pool = seed_database(&config).await;
+    let progress = IngestProgress::default();
+    let id = start_ingest_run(
+        "/v1/cryptocurrency/listings/latest",
+        "limit=4",
+        pool.clone(),
+    )
+    .await
+    .expect("Failed to start the run");
+    finish_ingest_run(id, &progress, None, pool.clone())
+        .await
+        .expect("Failed to finish the run");
+    let id = start_ingest_run("/v1/cryptocurrency/map", "", pool.clone())
+        .await
+        .expect("Failed to start the run");
+    finish_ingest_run(id, &progress, Some("timed out"), pool)
+        .await
+        .expect("Failed to finish the run");
+
+    if admin {
+        config.admin = Some(AdminConfig {
+            token: TOKEN.to_string(),
+        });
+    }
+
+    spawn_server(config).await
+}
+
+/// Sends a `GET` request to `url` with the bearer `token`, returning the status and the JSON body.
+async fn get_authorized(url: &str, token: &str) -> (u16, Value) {
+    let response = reqwest::Client::new()
+        .get(url)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Failed to send the request");
+    let status = response.status().as_u16();
+
+    (status, response.json().await.unwrap_or(Value::Null))
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_ingestion() {
+    let base_url = spawn(true).await;
+
+    let url = format!("{}/admin/ingestion", base_url);
+    let (status, body) = get_authorized(&url, TOKEN).await;
+    assert_eq!(status, 200);
+    // Newest first.
+    let runs = body.as_array().expect("Not a list of runs!");
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0]["endpoint"], "/v1/cryptocurrency/map");
+    assert_eq!(runs[0]["status"], "failed");
+    assert_eq!(runs[0]["error"], "timed out");
+
+    let url = format!("{}/admin/ingestion?status=succeeded", base_url);
+    let (status, body) = get_authorized(&url, TOKEN).await;
+    assert_eq!(status, 200);
+    let runs = body.as_array().expect("Not a list of runs!");
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0]["endpoint"], "/v1/cryptocurrency/listings/latest");
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_ingestion_bad_request() {
+    let base_url = spawn(true).await;
+
+    let url = format!("{}/admin/ingestion?status=pending", base_url);
+    let (status, _) = get_authorized(&url, TOKEN).await;
+    assert_eq!(status, 400);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_ingestion_unauthorized() {
+    let base_url = spawn(true).await;
+
+    let url = format!("{}/admin/ingestion", base_url);
+    let (status, _, _) = get(&url).await;
+    assert_eq!(status, 401);
+    let (status, _) = get_authorized(&url, "wrong-token").await;
+    assert_eq!(status, 401);
+
+    // Without a token in the configuration, the admin endpoints are not served at all.
+    let base_url = spawn(false).await;
+    let (status, _) = get_authorized(&format!("{}/admin/ingestion", base_url), TOKEN).await;
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{get, seed_database, seed_trending, spawn_server, test_config};
+use serde_json::Value;
+use serial_test::serial;
+
+/// Returns the symbols of the cryptocurrencies in `list`.
+fn symbols(list: &Value) -> Vec<&str> {
+    list.as_array()
+        .expect("Not a list of cryptocurrencies!")
+        .iter()
+        .map(|crypto| crypto["symbol"].as_str().unwrap())
+        .collect()
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_movers_from_local_listings() {
+    let config = test_config();
+    seed_database(&config).await;
+    let base_url = spawn_server(config).await;
+
+    let (status, _, body) = get(&format!("{}/crypto/movers", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["source"], "local");
+    assert_eq!(body["window"], "24h");
+    assert_eq!(symbols(&body["gainers"]), ["BNB"]);
+    assert_eq!(symbols(&body["losers"]), ["BTC", "ADA", "ETH"]);
+
+    let (status, _, body) = get(&format!("{}/crypto/movers?window=7d&limit=1", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(symbols(&body["gainers"]), ["BTC"]);
+    assert_eq!(symbols(&body["losers"]), ["ADA"]);
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_movers_from_snapshot() {
+    let config = test_config();
+    let pool = seed_database(&config).await;
+    seed_trending(&pool, "gainers").await;
+    seed_trending(&pool, "losers").await;
+    let base_url = spawn_server(config).await;
+
+    let (status, _, body) = get(&format!("{}/crypto/movers", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["source"], "upstream");
+    assert_eq!(symbols(&body["gainers"]), symbols(&body["losers"]));
+    assert_eq!(symbols(&body["gainers"])[0], "NEAR");
+
+    let (status, _, body) = get(&format!("{}/crypto/trending/gainers", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(symbols(&body)[0], "NEAR");
+
+    // No snapshot of the other feeds.
+    let (status, _, body) = get(&format!("{}/crypto/trending/latest", base_url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, Value::Array(vec![]));
+}
+
+#[actix_rt::test]
+#[serial]
+async fn serve_movers_bad_request() {
+    let config = test_config();
+    seed_database(&config).await;
+    let base_url = spawn_server(config).await;
+
+    let (status, _, _) = get(&format!("{}/crypto/movers?window=2w", base_url)).await;
+    assert_eq!(status, 400);
+
+    let (status, _, _) = get(&format!("{}/crypto/trending/unknown", base_url)).await;
+    assert_eq!(status, 404);
+}